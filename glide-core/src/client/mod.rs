@@ -3,10 +3,10 @@
 mod types;
 
 use crate::cluster_scan_container::insert_cluster_scan_cursor;
-use crate::compression::CompressionBackendType;
-use crate::compression::lz4_backend::Lz4Backend;
-use crate::compression::zstd_backend::ZstdBackend;
-use crate::compression::{CompressionConfig, CompressionManager};
+use crate::compression::{
+    CompressionConfig, CompressionManager, CompressionResult, hrandfield_returns_values,
+    process_custom_command_response_in_place, process_exec_response, set_returns_old_value,
+};
 use crate::scripts_container::get_script;
 use futures::FutureExt;
 use logger_core::{log_debug, log_error, log_info, log_warn};
@@ -18,8 +18,8 @@ use redis::cluster_routing::{
 };
 use redis::cluster_slotmap::ReadFromReplicaStrategy;
 use redis::{
-    ClusterScanArgs, Cmd, ErrorKind, FromRedisValue, PipelineRetryStrategy, PushInfo, RedisError,
-    RedisResult, RetryStrategy, ScanStateRC, Value,
+    ClusterScanArgs, Cmd, ErrorKind, FromRedisValue, Pipeline, PipelineRetryStrategy, PushInfo,
+    RedisError, RedisResult, RetryStrategy, ScanStateRC, Value,
 };
 pub use standalone_client::StandaloneClient;
 use std::io;
@@ -68,20 +68,88 @@ pub const DEFAULT_MAX_INFLIGHT_REQUESTS: u32 = 1000;
 /// and performance overhead.
 pub const CONNECTION_CHECKS_INTERVAL: Duration = Duration::from_secs(3);
 
+/// Arguments of a Redis command, without the command name
+fn command_args(cmd: &Cmd) -> impl Iterator<Item = &[u8]> {
+    cmd.args_iter().skip(1).filter_map(|arg| match arg {
+        redis::Arg::Simple(bytes) => Some(bytes),
+        redis::Arg::Cursor => None,
+    })
+}
+
 /// Extract RequestType from a Redis command for decompression processing
-/// SIMPLIFIED VERSION: Only supports basic GET commands for decompression.
+/// Only commands whose responses are decompressed are mapped.
 fn extract_request_type_from_cmd(cmd: &Cmd) -> Option<RequestType> {
     // Get the command name (first argument)
     let command_name = cmd.command()?;
     let command_str = String::from_utf8_lossy(&command_name).to_uppercase();
 
-    // Map command names to RequestType - only basic GET supported for decompression
+    // Map command names to RequestType - only commands with decompressed responses
     match command_str.as_str() {
         "GET" => Some(RequestType::Get),
+        "GETDEL" => Some(RequestType::GetDel),
+        "MGET" => Some(RequestType::MGet),
+        // SET ... GET replies with the previous value
+        "SET" if set_returns_old_value(command_args(cmd)) => Some(RequestType::Set),
+        "LINDEX" => Some(RequestType::LIndex),
+        "LRANGE" => Some(RequestType::LRange),
+        "LPOP" => Some(RequestType::LPop),
+        "RPOP" => Some(RequestType::RPop),
+        "LMOVE" => Some(RequestType::LMove),
+        "BLMOVE" => Some(RequestType::BLMove),
+        "RPOPLPUSH" => Some(RequestType::RPopLPush),
+        "BRPOPLPUSH" => Some(RequestType::BRPopLPush),
+        "LMPOP" => Some(RequestType::LMPop),
+        "BLMPOP" => Some(RequestType::BLMPop),
+        "BLPOP" => Some(RequestType::BLPop),
+        "BRPOP" => Some(RequestType::BRPop),
+        "ZMPOP" => Some(RequestType::ZMPop),
+        "GEOSEARCH" => Some(RequestType::GeoSearch),
+        "HGET" => Some(RequestType::HGet),
+        "HGETALL" => Some(RequestType::HGetAll),
+        "HMGET" => Some(RequestType::HMGet),
+        "HRANDFIELD" if hrandfield_returns_values(command_args(cmd)) => {
+            Some(RequestType::HRandField)
+        }
+        "HKEYS" => Some(RequestType::HKeys),
+        "HVALS" => Some(RequestType::HVals),
+        "JSON.MGET" => Some(RequestType::JsonMGet),
+        "SPOP" => Some(RequestType::SPop),
+        "XRANGE" => Some(RequestType::XRange),
+        "XREVRANGE" => Some(RequestType::XRevRange),
+        "XREAD" => Some(RequestType::XRead),
+        "XREADGROUP" => Some(RequestType::XReadGroup),
         _ => None, // Unknown command, no compression/decompression needed
     }
 }
 
+/// Decompresses the results of a batch, each according to the pipeline command that produced
+/// it. Replies of custom commands in the compression command table are matched by name.
+pub(crate) fn decompress_batch_results(
+    results: Vec<Value>,
+    pipeline: &Pipeline,
+    manager: &CompressionManager,
+) -> CompressionResult<Vec<Value>> {
+    let request_types: Vec<RequestType> = pipeline
+        .cmd_iter()
+        .map(|cmd| extract_request_type_from_cmd(cmd).unwrap_or(RequestType::CustomCommand))
+        .collect();
+    let mut results = process_exec_response(results, &request_types, Some(manager))?;
+
+    if !manager.command_table().is_empty() {
+        for ((result, cmd), request_type) in results
+            .iter_mut()
+            .zip(pipeline.cmd_iter())
+            .zip(request_types)
+        {
+            if matches!(request_type, RequestType::CustomCommand) {
+                let command_name = cmd.command().unwrap_or_default();
+                process_custom_command_response_in_place(result, &command_name, manager)?;
+            }
+        }
+    }
+    Ok(results)
+}
+
 /// A static Glide runtime instance
 static RUNTIME: OnceCell<GlideRt> = OnceCell::new();
 
@@ -669,8 +737,24 @@ impl Client {
                                     value // Return original value on decompression failure
                                 }
                             }
+                        } else if compression_manager.command_table().is_empty() {
+                            value
                         } else {
-                            value // No request type found, return original value
+                            // Custom commands registered in the compression command table.
+                            // Values that fail to decompress are left as received.
+                            let mut value = value;
+                            let command_name = cmd.command().unwrap_or_default();
+                            if let Err(e) = crate::compression::process_custom_command_response_in_place(
+                                &mut value,
+                                &command_name,
+                                compression_manager.as_ref()
+                            ) {
+                                log_warn(
+                                    "send_command_decompression",
+                                    format!("Failed to decompress response: {}", e),
+                                );
+                            }
+                            value
                         }
                     } else {
                         value // No compression manager, return original value
@@ -1626,12 +1710,7 @@ fn create_compression_manager(
         return Ok(None);
     }
 
-    let backend: Box<dyn crate::compression::CompressionBackend> = match config.backend {
-        CompressionBackendType::Zstd => Box::new(ZstdBackend::new()),
-        CompressionBackendType::Lz4 => Box::new(Lz4Backend::new()),
-    };
-
-    let manager = CompressionManager::new(backend, config).map_err(|e| {
+    let manager = CompressionManager::new(config.backend.make_backend(), config).map_err(|e| {
         ConnectionError::Configuration(format!("Failed to create compression manager: {}", e))
     })?;
 
@@ -2160,4 +2239,35 @@ mod tests {
             Some("test_name".to_string())
         );
     }
+
+    #[test]
+    fn test_decompress_batch_results_by_command() {
+        use crate::compression::CompressionManagerBuilder;
+        use redis::{Pipeline, Value};
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original = "batched value ".repeat(20).into_bytes();
+        let compressed = manager.compress_value(&original).into_owned();
+
+        let mut pipeline = Pipeline::new();
+        pipeline.cmd("GET").arg("key");
+        pipeline.cmd("DUMP").arg("key");
+        pipeline.cmd("SET").arg("key").arg("value");
+        let results = vec![
+            Value::BulkString(compressed.clone()),
+            Value::BulkString(compressed.clone()),
+            Value::Okay,
+        ];
+
+        // GET results are decompressed, while DUMP payloads are returned as received
+        let results = super::decompress_batch_results(results, &pipeline, &manager).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                Value::BulkString(original),
+                Value::BulkString(compressed),
+                Value::Okay,
+            ]
+        );
+    }
 }
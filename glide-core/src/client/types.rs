@@ -333,6 +333,7 @@ impl From<protobuf::ConnectionRequest> for ConnectionRequest {
                 backend,
                 compression_level: proto_config.compression_level,
                 min_compression_size: proto_config.min_compression_size as usize,
+                ..CompressionConfig::new(backend)
             }
         });
 
@@ -4,13 +4,26 @@
 //! for Valkey Glide client operations.
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::request_type::RequestType;
+use arc_swap::ArcSwap;
+#[cfg(feature = "bytes-api")]
+use bytes::Bytes;
+use logger_core::log_warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use telemetrylib::Telemetry;
 
-/// Detailed compression error with context for debugging
-#[derive(Debug, Clone, PartialEq)]
+/// Detailed compression error with context for debugging.
+///
+/// New variants may be added; match on [`CompressionError::category`] for stable handling.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum CompressionError {
     /// Compression operation failed with detailed context
     CompressionFailed {
@@ -28,7 +41,102 @@ pub enum CompressionError {
     /// Unsupported compression backend
     UnsupportedBackend { backend_name: String },
     /// Invalid compression configuration
-    InvalidConfiguration { backend: String, reason: String },
+    InvalidConfiguration {
+        backend: String,
+        reason: String,
+        /// The rejected configuration, when the error comes from validating one
+        config: Option<Box<CompressionConfig>>,
+    },
+    /// I/O error raised by a codec stream, converted with `?` in backend implementations
+    Io { source: Arc<std::io::Error> },
+    /// The backend failed its self-test, for example because the linked codec library
+    /// doesn't match the version the bindings were built for
+    BackendInitializationFailed { backend: String, reason: String },
+}
+
+impl PartialEq for CompressionError {
+    fn eq(&self, other: &Self) -> bool {
+        use CompressionError::*;
+
+        match (self, other) {
+            (
+                CompressionFailed {
+                    backend,
+                    level,
+                    data_size,
+                    reason,
+                },
+                CompressionFailed {
+                    backend: other_backend,
+                    level: other_level,
+                    data_size: other_data_size,
+                    reason: other_reason,
+                },
+            ) => {
+                backend == other_backend
+                    && level == other_level
+                    && data_size == other_data_size
+                    && reason == other_reason
+            }
+            (
+                DecompressionFailed {
+                    backend,
+                    data_size,
+                    reason,
+                },
+                DecompressionFailed {
+                    backend: other_backend,
+                    data_size: other_data_size,
+                    reason: other_reason,
+                },
+            ) => backend == other_backend && data_size == other_data_size && reason == other_reason,
+            (
+                UnsupportedBackend { backend_name },
+                UnsupportedBackend {
+                    backend_name: other_backend_name,
+                },
+            ) => backend_name == other_backend_name,
+            (
+                InvalidConfiguration {
+                    backend,
+                    reason,
+                    config,
+                },
+                InvalidConfiguration {
+                    backend: other_backend,
+                    reason: other_reason,
+                    config: other_config,
+                },
+            ) => backend == other_backend && reason == other_reason && config == other_config,
+            (
+                BackendInitializationFailed { backend, reason },
+                BackendInitializationFailed {
+                    backend: other_backend,
+                    reason: other_reason,
+                },
+            ) => backend == other_backend && reason == other_reason,
+            // `io::Error` isn't comparable, so I/O errors are equal when they report the same
+            // kind and message
+            (
+                Io { source },
+                Io {
+                    source: other_source,
+                },
+            ) => {
+                source.kind() == other_source.kind()
+                    && source.to_string() == other_source.to_string()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<std::io::Error> for CompressionError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io {
+            source: Arc::new(error),
+        }
+    }
 }
 
 impl std::fmt::Display for CompressionError {
@@ -73,18 +181,50 @@ impl std::fmt::Display for CompressionError {
             CompressionError::UnsupportedBackend { backend_name } => {
                 write!(f, "Unsupported compression backend: '{}'", backend_name)
             }
-            CompressionError::InvalidConfiguration { backend, reason } => {
+            CompressionError::InvalidConfiguration {
+                backend, reason, ..
+            } => {
                 write!(
                     f,
                     "Invalid compression configuration for '{}': {}",
                     backend, reason
                 )
             }
+            CompressionError::Io { source } => {
+                write!(f, "Compression I/O operation failed: {}", source)
+            }
+            CompressionError::BackendInitializationFailed { backend, reason } => {
+                write!(
+                    f,
+                    "Compression backend '{}' failed to initialize: {}",
+                    backend, reason
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompressionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CompressionError::Io { source } => Some(source.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for CompressionError {}
+/// Stable grouping of [`CompressionError`] variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The configuration is invalid
+    Config,
+    /// The backend failed to encode or decode the data
+    Codec,
+    /// The data failed an integrity check
+    Integrity,
+    /// The backend or data format isn't supported by this client
+    Unsupported,
+}
 
 impl CompressionError {
     pub fn compression_failed(
@@ -123,16 +263,61 @@ impl CompressionError {
         Self::InvalidConfiguration {
             backend: backend.into(),
             reason: reason.into(),
+            config: None,
+        }
+    }
+
+    pub fn backend_initialization_failed(backend: &str, reason: impl Into<String>) -> Self {
+        Self::BackendInitializationFailed {
+            backend: backend.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Attaches the rejected configuration to an `InvalidConfiguration` error.
+    /// Other errors are returned unchanged.
+    pub fn with_config(mut self, rejected: &CompressionConfig) -> Self {
+        if let Self::InvalidConfiguration { config, .. } = &mut self {
+            config.get_or_insert_with(|| Box::new(rejected.clone()));
+        }
+        self
+    }
+
+    /// Returns the configuration rejected by validation, if this error carries one
+    pub fn config(&self) -> Option<&CompressionConfig> {
+        match self {
+            CompressionError::InvalidConfiguration { config, .. } => config.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the category of this error
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            CompressionError::CompressionFailed { .. } => ErrorCategory::Codec,
+            CompressionError::DecompressionFailed { .. } => ErrorCategory::Codec,
+            CompressionError::InvalidConfiguration { .. } => ErrorCategory::Config,
+            CompressionError::UnsupportedBackend { .. } => ErrorCategory::Unsupported,
+            CompressionError::Io { .. } => ErrorCategory::Codec,
+            CompressionError::BackendInitializationFailed { .. } => ErrorCategory::Unsupported,
         }
     }
 
-    /// Returns the backend name associated with this error
+    /// Returns the underlying error when it is a `T`, for example the `io::Error` of an
+    /// `Io` error, so callers can inspect it without parsing the message
+    pub fn downcast_source<T: std::error::Error + 'static>(&self) -> Option<&T> {
+        std::error::Error::source(self)?.downcast_ref::<T>()
+    }
+
+    /// Returns the backend name associated with this error, empty for I/O errors
     pub fn backend(&self) -> &str {
         match self {
             CompressionError::CompressionFailed { backend, .. } => backend,
             CompressionError::DecompressionFailed { backend, .. } => backend,
             CompressionError::InvalidConfiguration { backend, .. } => backend,
             CompressionError::UnsupportedBackend { backend_name } => backend_name,
+            CompressionError::Io { .. } => "",
+            CompressionError::BackendInitializationFailed { backend, .. } => backend,
         }
     }
 }
@@ -163,7 +348,219 @@ pub trait CompressionBackend: Send + Sync + fmt::Debug {
     fn backend_name(&self) -> &'static str;
     fn default_level(&self) -> Option<i32>;
     fn backend_id(&self) -> u8;
+    /// Returns whether the backend accepts the given compression level
+    fn supports_level(&self, level: i32) -> bool;
     fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()>;
+
+    /// Returns an upper bound of the `compress` output size for an input of `input_len` bytes,
+    /// including the legacy header. The default is conservative for common block codecs.
+    fn max_compressed_bound(&self, input_len: usize) -> usize {
+        HEADER_SIZE + input_len + input_len / 255 + 16
+    }
+
+    /// Returns the backend name together with the id written into its headers
+    fn name_and_id(&self) -> (&'static str, u8) {
+        (self.backend_name(), self.backend_id())
+    }
+
+    /// Checks that `data` is a well-formed compressed value without returning its decompressed
+    /// contents. The default only checks the header; backends may also check the payload.
+    fn validate(&self, data: &[u8]) -> CompressionResult<()> {
+        if self.is_compressed(data) {
+            Ok(())
+        } else {
+            Err(CompressionError::decompression_failed(
+                self.backend_name(),
+                data.len(),
+                "data is not compressed or has invalid header",
+            ))
+        }
+    }
+
+    /// Compresses like [`Self::compress`], failing with a "compression timeout" error once
+    /// `deadline` has passed. Streaming backends check the deadline between chunks; the default
+    /// can only check it after compressing the whole value.
+    fn compress_with_deadline(
+        &self,
+        data: &[u8],
+        level: Option<i32>,
+        deadline: Instant,
+    ) -> CompressionResult<Vec<u8>> {
+        let compressed = self.compress(data, level)?;
+        if Instant::now() > deadline {
+            return Err(CompressionError::compression_failed(
+                self.backend_name(),
+                level,
+                data.len(),
+                "compression timeout",
+            ));
+        }
+        Ok(compressed)
+    }
+
+    /// Compresses like [`Self::compress`] into `output`, which is cleared first and keeps its
+    /// capacity so it can be reused. Streaming backends feed the encoder in bounded chunks,
+    /// without an intermediate buffer sized for the whole input; the default copies the
+    /// output of [`Self::compress`].
+    fn compress_stream(
+        &self,
+        data: &[u8],
+        level: Option<i32>,
+        output: &mut Vec<u8>,
+    ) -> CompressionResult<()> {
+        let compressed = self.compress(data, level)?;
+        output.clear();
+        output.extend_from_slice(&compressed);
+        Ok(())
+    }
+
+    /// Decompresses codec output written without the GLIDE header, as found in the payload of
+    /// a legacy-header value. The default adds a legacy header and calls [`Self::decompress`].
+    fn decompress_raw(&self, payload: &[u8]) -> CompressionResult<Vec<u8>> {
+        let mut framed = Vec::with_capacity(HEADER_SIZE + payload.len());
+        framed.extend_from_slice(&create_header(self.backend_id()));
+        framed.extend_from_slice(payload);
+        self.decompress(&framed)
+    }
+
+    /// Returns the latest epoch of the pre-shared dictionary registered under `dict_id`,
+    /// `None` when there is none
+    fn dictionary_epoch(&self, _dict_id: DictId) -> Option<DictEpoch> {
+        None
+    }
+
+    /// Compresses like [`Self::compress`] with the pre-shared dictionary registered under
+    /// `dict_id` and `epoch`. The output carries the legacy header, the caller records the
+    /// dictionary. The default fails, for backends without dictionary support.
+    fn compress_with_dictionary(
+        &self,
+        data: &[u8],
+        level: Option<i32>,
+        dict_id: DictId,
+        epoch: DictEpoch,
+    ) -> CompressionResult<Vec<u8>> {
+        Err(CompressionError::compression_failed(
+            self.backend_name(),
+            level,
+            data.len(),
+            format!("dictionary {} epoch {} is not registered", dict_id, epoch),
+        ))
+    }
+}
+
+/// Application-specific decision of which command arguments are compressed.
+///
+/// When set on a [`CompressionManager`], the policy replaces the size thresholds of the
+/// configuration for command arguments. Empty values and values that don't shrink are still
+/// stored raw.
+pub trait CompressionPolicy: Send + Sync + fmt::Debug {
+    fn should_compress(&self, key: &[u8], value: &[u8], request_type: RequestType) -> bool;
+}
+
+/// Content-based check of whether a value is likely to shrink, consulted by a
+/// [`CompressionManager`] in addition to the size thresholds or the policy
+pub trait ContentClassifier: Send + Sync + fmt::Debug {
+    fn is_worth_compressing(&self, data: &[u8]) -> bool;
+}
+
+/// Rejects values whose byte distribution looks random, such as encrypted or already
+/// compressed data, by computing the Shannon entropy of a leading sample
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShannonEntropyClassifier {
+    /// Values with an entropy above this many bits per byte are not compressed (at most 8)
+    pub max_bits_per_byte: f64,
+    /// Number of leading bytes the entropy is computed on
+    pub sample_size: usize,
+}
+
+impl ShannonEntropyClassifier {
+    pub fn new(max_bits_per_byte: f64) -> Self {
+        Self {
+            max_bits_per_byte,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the entropy in bits per byte of the leading sample of `data`
+    pub fn entropy(&self, data: &[u8]) -> f64 {
+        let sample = &data[..data.len().min(self.sample_size)];
+        if sample.is_empty() {
+            return 0.0;
+        }
+
+        let mut counts = [0usize; 256];
+        for &byte in sample {
+            counts[byte as usize] += 1;
+        }
+        let len = sample.len() as f64;
+        counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+}
+
+impl Default for ShannonEntropyClassifier {
+    fn default() -> Self {
+        Self {
+            max_bits_per_byte: 7.5,
+            sample_size: 4096,
+        }
+    }
+}
+
+impl ContentClassifier for ShannonEntropyClassifier {
+    fn is_worth_compressing(&self, data: &[u8]) -> bool {
+        self.entropy(data) <= self.max_bits_per_byte
+    }
+}
+
+/// Rejects values starting with the signature of a common compressed format:
+/// gzip, zip, PNG or JPEG
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MagicByteSniffer;
+
+impl MagicByteSniffer {
+    const SIGNATURES: [&'static [u8]; 4] = [
+        // gzip
+        &[0x1f, 0x8b],
+        // zip
+        b"PK\x03\x04",
+        // PNG
+        &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'],
+        // JPEG
+        &[0xff, 0xd8, 0xff],
+    ];
+}
+
+impl ContentClassifier for MagicByteSniffer {
+    fn is_worth_compressing(&self, data: &[u8]) -> bool {
+        !Self::SIGNATURES
+            .iter()
+            .any(|signature| data.starts_with(signature))
+    }
+}
+
+/// Asserts that a backend's id matches the [`CompressionBackendType`] named by `backend_name()`.
+///
+/// Backends whose name isn't a known backend type are not checked.
+pub fn assert_backend_consistency(backend: &dyn CompressionBackend) {
+    let (name, id) = backend.name_and_id();
+    if let Ok(backend_type) = name.parse::<CompressionBackendType>() {
+        assert_eq!(
+            backend_type.backend_id(),
+            id,
+            "backend '{}' reports id {} but {} uses id {}",
+            name,
+            id,
+            backend_type,
+            backend_type.backend_id()
+        );
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -173,6 +570,18 @@ pub enum CompressionBackendType {
 }
 
 impl CompressionBackendType {
+    /// Returns every backend type
+    pub fn all() -> [CompressionBackendType; 2] {
+        [CompressionBackendType::Zstd, CompressionBackendType::Lz4]
+    }
+
+    /// Returns the backend type written into headers as `id`, the inverse of [`Self::backend_id`]
+    pub fn by_id(id: u8) -> Option<Self> {
+        Self::all()
+            .into_iter()
+            .find(|backend| backend.backend_id() == id)
+    }
+
     pub fn backend_id(&self) -> u8 {
         match self {
             CompressionBackendType::Zstd => 0x01,
@@ -193,6 +602,134 @@ impl CompressionBackendType {
             CompressionBackendType::Lz4 => Some(0), // LZ4 default compression
         }
     }
+
+    /// Creates a new instance of the backend implementation for this type. Backends missing
+    /// from [`compiled_backends`] fail every operation with `UnsupportedBackend`.
+    pub fn make_backend(&self) -> Box<dyn CompressionBackend> {
+        match self {
+            #[cfg(any(feature = "zstd", feature = "wasm"))]
+            CompressionBackendType::Zstd => Box::new(zstd_backend::ZstdBackend::new()),
+            #[cfg(feature = "lz4")]
+            CompressionBackendType::Lz4 => Box::new(lz4_backend::Lz4Backend::new()),
+            #[allow(unreachable_patterns)]
+            backend_type => Box::new(UnavailableBackend(*backend_type)),
+        }
+    }
+}
+
+/// Stand-in for a backend whose feature is disabled
+#[derive(Debug)]
+struct UnavailableBackend(CompressionBackendType);
+
+impl UnavailableBackend {
+    fn unsupported(&self) -> CompressionError {
+        CompressionError::unsupported_backend(format!("{} support not compiled in", self.0))
+    }
+}
+
+impl CompressionBackend for UnavailableBackend {
+    fn compress(&self, _data: &[u8], _level: Option<i32>) -> CompressionResult<Vec<u8>> {
+        Err(self.unsupported())
+    }
+
+    fn decompress(&self, _data: &[u8]) -> CompressionResult<Vec<u8>> {
+        Err(self.unsupported())
+    }
+
+    fn is_compressed(&self, data: &[u8]) -> bool {
+        has_magic_header(data) && extract_backend_id(data) == Some(self.backend_id())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.0.backend_name()
+    }
+
+    fn default_level(&self) -> Option<i32> {
+        self.0.default_level()
+    }
+
+    fn backend_id(&self) -> u8 {
+        self.0.backend_id()
+    }
+
+    fn supports_level(&self, _level: i32) -> bool {
+        false
+    }
+
+    fn validate_compression_level(&self, _level: Option<i32>) -> CompressionResult<()> {
+        Err(self.unsupported())
+    }
+}
+
+/// Backend-independent compression effort between 0.0 (fastest) and 1.0 (best ratio),
+/// mapped onto the level scale of each backend by [`Self::as_backend_level`]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct CompressionLevel(f32);
+
+impl CompressionLevel {
+    pub const FASTEST: CompressionLevel = CompressionLevel(0.0);
+    pub const BALANCED: CompressionLevel = CompressionLevel(0.5);
+    pub const BEST: CompressionLevel = CompressionLevel(1.0);
+
+    /// Creates a level from an effort value, clamped to `0.0..=1.0`. NaN is treated as 0.0.
+    pub fn new(effort: f32) -> Self {
+        if effort.is_nan() {
+            return Self::FASTEST;
+        }
+        Self(effort.clamp(0.0, 1.0))
+    }
+
+    pub fn effort(&self) -> f32 {
+        self.0
+    }
+
+    /// Returns the level of `backend` matching this effort, or `None` if the backend has no
+    /// configurable level. zstd maps onto 1..=22 and lz4 onto its high compression levels 0..=12.
+    pub fn as_backend_level(&self, backend: CompressionBackendType) -> Option<i32> {
+        let (min, max) = match backend {
+            CompressionBackendType::Zstd => (1, 22),
+            CompressionBackendType::Lz4 => (0, 12),
+        };
+        let level = (self.0 * max as f32).round() as i32;
+        Some(level.clamp(min, max))
+    }
+}
+
+/// Keyed store of instantiated backends, so that managers sharing a backend type reuse one
+/// instance instead of calling [`CompressionBackendType::make_backend`] each time
+#[derive(Debug, Default)]
+pub struct BackendCache {
+    backends: std::sync::Mutex<HashMap<CompressionBackendType, Arc<dyn CompressionBackend>>>,
+}
+
+impl BackendCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached backend of this type, creating it on first use
+    pub fn get_or_init(&self, backend_type: CompressionBackendType) -> Arc<dyn CompressionBackend> {
+        let mut backends = self
+            .backends
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        backends
+            .entry(backend_type)
+            .or_insert_with(|| Arc::from(backend_type.make_backend()))
+            .clone()
+    }
+}
+
+/// Returns the backends compiled into this binary: zstd with the `zstd` or `wasm` feature,
+/// lz4 with the `lz4` feature
+pub fn compiled_backends() -> Vec<CompressionBackendType> {
+    CompressionBackendType::all()
+        .into_iter()
+        .filter(|backend| match backend {
+            CompressionBackendType::Zstd => cfg!(any(feature = "zstd", feature = "wasm")),
+            CompressionBackendType::Lz4 => cfg!(feature = "lz4"),
+        })
+        .collect()
 }
 
 impl std::fmt::Display for CompressionBackendType {
@@ -213,12 +750,124 @@ impl std::str::FromStr for CompressionBackendType {
     }
 }
 
+/// Client-side compression settings.
+///
+/// Note that server-side introspection reflects the stored (compressed) bytes: for example,
+/// `OBJECT ENCODING` on a compressed value reports the encoding of the compressed blob
+/// (typically "raw" instead of "embstr" or "int"), and bit commands such as `BITCOUNT`
+/// operate on the compressed representation.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CompressionConfig {
     pub enabled: bool,
     pub backend: CompressionBackendType,
     pub compression_level: Option<i32>,
     pub min_compression_size: usize,
+    /// Values larger than this are sent uncompressed. `None` means no upper bound.
+    pub max_compression_size: Option<usize>,
+    /// Integrity information written into the header of newly compressed values
+    pub integrity_mode: IntegrityMode,
+    /// Compressed results larger than this are discarded and the value is sent raw.
+    /// `None` means no limit on the compressed output size.
+    pub max_output_size: Option<usize>,
+    /// Compressed results saving fewer bytes than this are discarded and the value is sent raw.
+    /// The header overhead counts against the savings. `None` keeps any size reduction.
+    pub min_savings_bytes: Option<usize>,
+    /// Values starting with this prefix are never compressed and are stored as-is,
+    /// for example blobs the application already compressed.
+    pub no_compress_sentinel: Option<Vec<u8>>,
+    /// Called with the underlying error whenever compression or decompression falls back
+    /// to the original data
+    pub on_fallback: Option<FallbackHook>,
+    /// Bounds of the cache of recently decompressed values. `None` disables the cache.
+    /// Only takes effect when the `cache` feature is enabled.
+    pub decompression_cache: Option<DecompressionCacheConfig>,
+    /// Records the compression level in the header of newly compressed values.
+    /// Informational only, see [`extract_level`].
+    pub record_level: bool,
+    /// Records the payload length in the header of newly compressed values, so that values
+    /// built by appending compressed frames can be decompressed as a whole
+    pub multi_frame: bool,
+    /// Also compresses hash field names written by HSET and decompresses the field names
+    /// returned by HGETALL and HKEYS. Off by default, since compressed field names can no
+    /// longer be matched by commands taking a field argument, such as HEXISTS.
+    pub compress_hash_fields: bool,
+    /// Never compresses values that parse as an integer or a float, so that counters stay
+    /// usable by INCR, INCRBYFLOAT and similar commands
+    pub skip_numeric_values: bool,
+    /// Compression taking longer than this is aborted and the value is sent raw.
+    /// `None` means no limit.
+    pub compress_timeout: Option<Duration>,
+    /// Positions within the ARGV arguments of EVAL, EVALSHA and FCALL to compress, counted
+    /// from 0 (`ARGV[1]` in Lua is position 0). Empty by default. KEYS are never compressed.
+    /// Only useful with cooperating scripts that decompress these arguments server-side.
+    pub compress_eval_argv_indices: Vec<usize>,
+    /// Only decompresses values whose header declares the original length, checks the
+    /// decoded output against it whatever the backend, and rejects output that still carries
+    /// a compression header. Protects against values wrapped twice or by another tool.
+    pub strict_decompress: bool,
+    /// Compresses every non-empty value regardless of `min_compression_size`, and keeps the
+    /// result even when it isn't smaller. `enabled`, `max_compression_size` and
+    /// `max_output_size` still apply. Meant for tests and benchmarks, not for production use.
+    pub force_compress: bool,
+    /// Bytes written before the compression header of newly compressed values, for example
+    /// the metadata of a larger envelope. Values starting with it have it skipped on decode;
+    /// values without it are still read. Empty by default.
+    pub header_prefix: Vec<u8>,
+    /// Pre-shared dictionary used to compress the values of each command, for example one
+    /// trained on session JSON for SET and another on metrics for HSET. The dictionaries are
+    /// registered with the backend under these ids, and the id is recorded in the header so
+    /// clients holding the same dictionaries can decode the values. Values compressed with a
+    /// dictionary are compressed in one shot, without `compress_timeout`. Empty by default.
+    pub command_dictionaries: HashMap<RequestType, DictId>,
+    /// Values larger than the chunk size are split into independently compressed frames
+    /// recording their length, see [`ChunkingConfig`]. `None` compresses values whole.
+    pub chunking: Option<ChunkingConfig>,
+    /// Values without a compression header are passed to the raw decoder of the configured
+    /// backend, then of the read backends, and replaced by the first successful output. For
+    /// reading legacy data written without headers. Off by default: zstd output is recognized
+    /// by its frame magic number, but lz4 blocks have none and plain values may happen to
+    /// decode.
+    pub try_headerless_backends: bool,
+}
+
+/// Splitting of large values into chunks compressed as separate frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingConfig {
+    /// Size of each chunk but the last one
+    pub chunk_size: usize,
+    /// Compresses and decompresses the chunks of a value on several threads
+    pub parallel: bool,
+}
+
+/// Callback invoked with the error that caused a fallback to the original data
+#[derive(Clone)]
+pub struct FallbackHook(pub Arc<dyn Fn(&CompressionError) + Send + Sync>);
+
+impl FallbackHook {
+    pub fn call(&self, error: &CompressionError) {
+        (self.0)(error)
+    }
+}
+
+impl fmt::Debug for FallbackHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FallbackHook")
+    }
+}
+
+impl PartialEq for FallbackHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Limits for the cache of recently decompressed values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecompressionCacheConfig {
+    /// Maximum number of cached values
+    pub max_entries: usize,
+    /// Maximum total size in bytes of the cached values, compressed and decompressed
+    pub max_bytes: usize,
 }
 
 impl CompressionConfig {
@@ -228,15 +877,33 @@ impl CompressionConfig {
             backend,
             compression_level: backend.default_level(),
             min_compression_size: 64,
+            max_compression_size: None,
+            integrity_mode: IntegrityMode::None,
+            max_output_size: None,
+            min_savings_bytes: None,
+            no_compress_sentinel: None,
+            on_fallback: None,
+            decompression_cache: None,
+            record_level: false,
+            multi_frame: false,
+            compress_hash_fields: false,
+            skip_numeric_values: false,
+            compress_timeout: None,
+            compress_eval_argv_indices: Vec::new(),
+            strict_decompress: false,
+            force_compress: false,
+            header_prefix: Vec::new(),
+            command_dictionaries: HashMap::new(),
+            chunking: None,
+            try_headerless_backends: false,
         }
     }
 
     pub fn disabled() -> Self {
         Self {
             enabled: false,
-            backend: CompressionBackendType::Zstd,
             compression_level: None,
-            min_compression_size: 64,
+            ..Self::new(CompressionBackendType::Zstd)
         }
     }
 
@@ -245,206 +912,2574 @@ impl CompressionConfig {
         self
     }
 
+    /// Sets the compression level from a backend-independent effort, see [`CompressionLevel`]
+    pub fn with_effort(mut self, effort: f32) -> Self {
+        self.compression_level = CompressionLevel::new(effort).as_backend_level(self.backend);
+        self
+    }
+
     pub fn with_min_compression_size(mut self, size: usize) -> Self {
         self.min_compression_size = size;
         self
     }
 
-    pub fn validate(&self) -> CompressionResult<()> {
-        if self.min_compression_size < MIN_COMPRESSED_SIZE {
-            return Err(CompressionError::invalid_configuration(
-                self.backend.backend_name(),
-                format!(
-                    "min_compression_size ({}) must be at least {}",
-                    self.min_compression_size, MIN_COMPRESSED_SIZE
-                ),
-            ));
-        }
+    pub fn with_max_compression_size(mut self, size: Option<usize>) -> Self {
+        self.max_compression_size = size;
+        self
+    }
 
-        Ok(())
+    pub fn with_integrity_mode(mut self, mode: IntegrityMode) -> Self {
+        self.integrity_mode = mode;
+        self
     }
 
-    pub fn should_compress(&self, data_size: usize) -> bool {
-        self.enabled && data_size >= self.min_compression_size
+    pub fn with_max_output_size(mut self, size: Option<usize>) -> Self {
+        self.max_output_size = size;
+        self
     }
-}
 
-impl Default for CompressionConfig {
-    fn default() -> Self {
-        Self::disabled()
+    pub fn with_min_savings_bytes(mut self, bytes: Option<usize>) -> Self {
+        self.min_savings_bytes = bytes;
+        self
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum CommandCompressionBehavior {
-    CompressValues,
-    DecompressValues,
-    NoCompression,
-}
+    pub fn with_no_compress_sentinel(mut self, sentinel: impl Into<Vec<u8>>) -> Self {
+        self.no_compress_sentinel = Some(sentinel.into());
+        self
+    }
 
-impl CommandCompressionBehavior {
-    pub fn description(&self) -> &'static str {
-        match self {
-            CommandCompressionBehavior::CompressValues => {
-                "Compress values before sending to server"
-            }
-            CommandCompressionBehavior::DecompressValues => {
-                "Decompress values after receiving from server"
-            }
-            CommandCompressionBehavior::NoCompression => "No compression processing required",
-        }
+    pub fn with_on_fallback(
+        mut self,
+        hook: impl Fn(&CompressionError) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_fallback = Some(FallbackHook(Arc::new(hook)));
+        self
     }
-}
 
-impl std::fmt::Display for CommandCompressionBehavior {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CommandCompressionBehavior::CompressValues => write!(f, "CompressValues"),
-            CommandCompressionBehavior::DecompressValues => write!(f, "DecompressValues"),
-            CommandCompressionBehavior::NoCompression => write!(f, "NoCompression"),
-        }
+    pub fn with_decompression_cache(mut self, cache: Option<DecompressionCacheConfig>) -> Self {
+        self.decompression_cache = cache;
+        self
     }
-}
 
-#[derive(Debug)]
-pub struct CompressionManager {
-    backend: Box<dyn CompressionBackend>,
-    config: CompressionConfig,
-}
+    pub fn with_record_level(mut self, record_level: bool) -> Self {
+        self.record_level = record_level;
+        self
+    }
 
-impl CompressionManager {
-    pub fn new(
-        backend: Box<dyn CompressionBackend>,
-        config: CompressionConfig,
-    ) -> CompressionResult<Self> {
-        config.validate()?;
+    pub fn with_multi_frame(mut self, multi_frame: bool) -> Self {
+        self.multi_frame = multi_frame;
+        self
+    }
 
-        if backend.backend_id() != config.backend.backend_id() {
-            return Err(CompressionError::invalid_configuration(
-                config.backend.backend_name(),
-                format!(
-                    "backend mismatch: expected {} (id: {}), got backend with id {}",
-                    config.backend.backend_name(),
-                    config.backend.backend_id(),
-                    backend.backend_id()
+    pub fn with_compress_hash_fields(mut self, compress_hash_fields: bool) -> Self {
+        self.compress_hash_fields = compress_hash_fields;
+        self
+    }
+
+    pub fn with_skip_numeric_values(mut self, skip_numeric_values: bool) -> Self {
+        self.skip_numeric_values = skip_numeric_values;
+        self
+    }
+
+    pub fn with_compress_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.compress_timeout = timeout;
+        self
+    }
+
+    pub fn with_compress_eval_argv_indices(mut self, indices: impl Into<Vec<usize>>) -> Self {
+        self.compress_eval_argv_indices = indices.into();
+        self
+    }
+
+    pub fn with_strict_decompress(mut self, strict_decompress: bool) -> Self {
+        self.strict_decompress = strict_decompress;
+        self
+    }
+
+    pub fn with_force_compress(mut self, force_compress: bool) -> Self {
+        self.force_compress = force_compress;
+        self
+    }
+
+    pub fn with_header_prefix(mut self, header_prefix: impl Into<Vec<u8>>) -> Self {
+        self.header_prefix = header_prefix.into();
+        self
+    }
+
+    /// Compresses the values of `request_type` with the dictionary registered as `dict_id`
+    pub fn with_command_dictionary(mut self, request_type: RequestType, dict_id: DictId) -> Self {
+        self.command_dictionaries.insert(request_type, dict_id);
+        self
+    }
+
+    /// Splits values larger than `chunk_size` into chunks compressed as separate frames,
+    /// on several threads when `parallel` is set. Zstd only.
+    pub fn with_chunking(mut self, chunk_size: usize, parallel: bool) -> Self {
+        self.chunking = Some(ChunkingConfig {
+            chunk_size,
+            parallel,
+        });
+        self
+    }
+
+    pub fn with_try_headerless_backends(mut self, try_headerless_backends: bool) -> Self {
+        self.try_headerless_backends = try_headerless_backends;
+        self
+    }
+
+    /// Returns the header flags written for every newly compressed value. Values of commands
+    /// listed in `command_dictionaries` also carry [`FLAG_DICTIONARY`].
+    pub fn header_flags(&self) -> u8 {
+        let mut flags = self.integrity_mode.header_flags();
+        if self.record_level {
+            flags |= FLAG_LEVEL;
+        }
+        if self.multi_frame {
+            flags |= FLAG_FRAME_LENGTH;
+        }
+        flags
+    }
+
+    /// Returns a checksum of the settings that determine which arguments are compressed and
+    /// how they are encoded. Clients with the same fingerprint write interchangeable values;
+    /// thresholds, callbacks and the cache are not included.
+    pub fn fingerprint(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&[
+            self.enabled as u8,
+            self.backend.backend_id(),
+            self.header_flags(),
+            self.compress_hash_fields as u8,
+            self.skip_numeric_values as u8,
+        ]);
+        if let Some(level) = self.compression_level {
+            hasher.update(&level.to_le_bytes());
+        }
+        for index in &self.compress_eval_argv_indices {
+            hasher.update(&(*index as u64).to_le_bytes());
+        }
+        hasher.update(&(self.header_prefix.len() as u64).to_le_bytes());
+        hasher.update(&self.header_prefix);
+        let mut dictionaries: Vec<_> = self
+            .command_dictionaries
+            .iter()
+            .map(|(request_type, dict_id)| (*request_type as u32, *dict_id))
+            .collect();
+        dictionaries.sort_unstable();
+        for (request_type, dict_id) in dictionaries {
+            hasher.update(&request_type.to_le_bytes());
+            hasher.update(&dict_id.to_le_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Strict validation for configurations built ahead of the manager: fails early with
+    /// `UnsupportedBackend` when the selected backend isn't compiled in, then runs [`Self::validate`]
+    pub fn validate_buildable(&self) -> CompressionResult<()> {
+        if !compiled_backends().contains(&self.backend) {
+            return Err(CompressionError::unsupported_backend(format!(
+                "{} support not compiled in",
+                self.backend
+            )));
+        }
+        self.validate()
+    }
+
+    /// Checks the configuration. `InvalidConfiguration` errors carry a copy of the rejected
+    /// configuration, see [`CompressionError::config`].
+    pub fn validate(&self) -> CompressionResult<()> {
+        self.check().map_err(|err| err.with_config(self))
+    }
+
+    fn check(&self) -> CompressionResult<()> {
+        if self.min_compression_size < MIN_COMPRESSED_SIZE {
+            return Err(CompressionError::invalid_configuration(
+                self.backend.backend_name(),
+                format!(
+                    "min_compression_size ({}) must be at least {}",
+                    self.min_compression_size, MIN_COMPRESSED_SIZE
+                ),
+            ));
+        }
+
+        // The minimum is ignored when compression is forced
+        if let Some(max_size) = self.max_compression_size
+            && max_size < self.min_compression_size
+            && !self.force_compress
+        {
+            return Err(CompressionError::invalid_configuration(
+                self.backend.backend_name(),
+                format!(
+                    "max_compression_size ({}) must not be smaller than min_compression_size ({})",
+                    max_size, self.min_compression_size
+                ),
+            ));
+        }
+
+        if self
+            .no_compress_sentinel
+            .as_ref()
+            .is_some_and(|sentinel| sentinel.is_empty())
+        {
+            return Err(CompressionError::invalid_configuration(
+                self.backend.backend_name(),
+                "no_compress_sentinel must not be empty",
+            ));
+        }
+
+        if let Some(chunking) = &self.chunking {
+            if chunking.chunk_size < MIN_COMPRESSED_SIZE {
+                return Err(CompressionError::invalid_configuration(
+                    self.backend.backend_name(),
+                    format!(
+                        "chunk_size ({}) must be at least {}",
+                        chunking.chunk_size, MIN_COMPRESSED_SIZE
+                    ),
+                ));
+            }
+            if self.backend != CompressionBackendType::Zstd {
+                return Err(CompressionError::invalid_configuration(
+                    self.backend.backend_name(),
+                    "chunking requires a backend reading multiple frames",
+                ));
+            }
+        }
+
+        if let Some(cache) = self.decompression_cache
+            && cache.max_entries == 0
+        {
+            return Err(CompressionError::invalid_configuration(
+                self.backend.backend_name(),
+                "decompression_cache max_entries must be greater than 0",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether a compressed result should be kept instead of the original value
+    pub fn should_keep_compressed(&self, original_size: usize, compressed_size: usize) -> bool {
+        (self.force_compress
+            || (compressed_size < original_size
+                && self
+                    .min_savings_bytes
+                    .is_none_or(|min_savings| original_size - compressed_size >= min_savings)))
+            && self
+                .max_output_size
+                .is_none_or(|max_size| compressed_size <= max_size)
+    }
+
+    /// Returns whether the value is eligible for compression, checking its size, the
+    /// opt-out sentinel and, when configured, whether it is numeric
+    pub fn should_compress_value(&self, value: &[u8]) -> bool {
+        self.should_compress(value.len())
+            && self
+                .no_compress_sentinel
+                .as_ref()
+                .is_none_or(|sentinel| !value.starts_with(sentinel))
+            && !(self.skip_numeric_values && is_numeric(value))
+    }
+
+    /// Returns whether a value of `data_size` bytes is eligible for compression.
+    /// Empty values are never framed, whatever the configured thresholds.
+    pub fn should_compress(&self, data_size: usize) -> bool {
+        self.enabled
+            && data_size > 0
+            && (self.force_compress || data_size >= self.min_compression_size)
+            && self
+                .max_compression_size
+                .is_none_or(|max_size| data_size <= max_size)
+    }
+
+    /// Returns `data` without the configured header prefix, or unchanged when it doesn't
+    /// start with it
+    pub fn strip_header_prefix<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        data.strip_prefix(self.header_prefix.as_slice())
+            .unwrap_or(data)
+    }
+}
+
+/// Prepends the configured header prefix to a compressed value
+fn with_header_prefix(config: &CompressionConfig, compressed: Vec<u8>) -> Vec<u8> {
+    if config.header_prefix.is_empty() {
+        return compressed;
+    }
+    let mut prefixed = Vec::with_capacity(config.header_prefix.len() + compressed.len());
+    prefixed.extend_from_slice(&config.header_prefix);
+    prefixed.extend_from_slice(&compressed);
+    prefixed
+}
+
+/// Applies `f` to each item in order, spreading the items over scoped threads when
+/// `parallel` is set. Stops at the first error.
+fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    parallel: bool,
+    f: impl Fn(&T) -> CompressionResult<R> + Sync,
+) -> CompressionResult<Vec<R>> {
+    let threads = std::thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(items.len());
+    if !parallel || threads < 2 {
+        return items.iter().map(f).collect();
+    }
+
+    let f = &f;
+    let groups = std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(items.len().div_ceil(threads))
+            .map(|group| {
+                scope.spawn(move || group.iter().map(f).collect::<CompressionResult<Vec<R>>>())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect::<CompressionResult<Vec<_>>>()
+    })?;
+    Ok(groups.into_iter().flatten().collect())
+}
+
+/// Returns the payload of a value in the GLIDE format, best-effort when its header is damaged
+fn strip_header(data: &[u8]) -> &[u8] {
+    match split_frame(data) {
+        Some((_, payload)) => payload,
+        None => data.get(HEADER_SIZE..).unwrap_or_default(),
+    }
+}
+
+/// Returns whether the value parses as an integer or a float
+fn is_numeric(value: &[u8]) -> bool {
+    std::str::from_utf8(value)
+        .is_ok_and(|text| text.parse::<i64>().is_ok() || text.parse::<f64>().is_ok())
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self::disabled()
+    }
+}
+
+/// Workload characteristics used to pick a recommended [`CompressionConfig`].
+/// Serialized as the profile names `low-latency`, `balanced`, `max-ratio` and `text-heavy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkloadHint {
+    /// Favor speed: fast levels and only large values are compressed
+    LowLatency,
+    /// Balance between speed and compression ratio (backend defaults)
+    Balanced,
+    /// Favor compression ratio: slow levels and small values are compressed too
+    MaxRatio,
+    /// Mostly textual values (JSON, logs, HTML) that compress well at moderate levels
+    TextHeavy,
+}
+
+impl CompressionConfig {
+    /// Returns a recommended starting configuration for the given backend and workload
+    pub fn recommended(backend: CompressionBackendType, hint: WorkloadHint) -> Self {
+        let (level, min_size) = match (backend, hint) {
+            (CompressionBackendType::Zstd, WorkloadHint::LowLatency) => (1, 256),
+            (CompressionBackendType::Zstd, WorkloadHint::Balanced) => (3, 64),
+            (CompressionBackendType::Zstd, WorkloadHint::MaxRatio) => (19, 32),
+            (CompressionBackendType::Zstd, WorkloadHint::TextHeavy) => (6, 128),
+            // LZ4: negative levels select the fast mode, positive levels the high compression mode
+            (CompressionBackendType::Lz4, WorkloadHint::LowLatency) => (-1, 256),
+            (CompressionBackendType::Lz4, WorkloadHint::Balanced) => (0, 64),
+            (CompressionBackendType::Lz4, WorkloadHint::MaxRatio) => (12, 32),
+            (CompressionBackendType::Lz4, WorkloadHint::TextHeavy) => (9, 128),
+        };
+
+        Self::new(backend)
+            .with_compression_level(Some(level))
+            .with_min_compression_size(min_size)
+    }
+}
+
+/// Serialized form of a [`CompressionConfig`]: a named profile, see [`WorkloadHint`], with
+/// fields overriding the profile's defaults
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SerializedConfig {
+    profile: Option<WorkloadHint>,
+    backend: Option<String>,
+    enabled: Option<bool>,
+    level: Option<i32>,
+    min_size: Option<usize>,
+    max_size: Option<usize>,
+}
+
+/// Deserialized from `{ "profile": "low-latency", "min_size": 256 }` and similar: the profile
+/// is expanded with [`CompressionConfig::recommended`], zstd unless `backend` is given, then
+/// `enabled`, `level`, `min_size` and `max_size` override it. Without a profile the backend
+/// defaults apply. The result isn't validated until it is given to a manager.
+impl<'de> Deserialize<'de> for CompressionConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let serialized = SerializedConfig::deserialize(deserializer)?;
+        let backend = match serialized.backend {
+            Some(name) => name.parse().map_err(serde::de::Error::custom)?,
+            None => CompressionBackendType::Zstd,
+        };
+
+        let mut config = match serialized.profile {
+            Some(hint) => Self::recommended(backend, hint),
+            None => Self::new(backend),
+        };
+        if let Some(enabled) = serialized.enabled {
+            config.enabled = enabled;
+        }
+        if let Some(level) = serialized.level {
+            config.compression_level = Some(level);
+        }
+        if let Some(min_size) = serialized.min_size {
+            config.min_compression_size = min_size;
+        }
+        if let Some(max_size) = serialized.max_size {
+            config.max_compression_size = Some(max_size);
+        }
+        Ok(config)
+    }
+}
+
+/// Integrity information stored alongside compressed data.
+///
+/// The mode only affects how new values are written. Decompression always enforces
+/// whatever the header of the stored value declares, so values written with different
+/// modes can be read by the same manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IntegrityMode {
+    /// No integrity information (legacy header)
+    #[default]
+    None,
+    /// Store the original length, which is verified after decompression
+    LengthOnly,
+    /// Store the original length and a CRC32 checksum of the original data
+    Crc32,
+}
+
+impl IntegrityMode {
+    /// Returns the header flags written for this mode
+    pub fn header_flags(&self) -> u8 {
+        match self {
+            IntegrityMode::None => 0,
+            IntegrityMode::LengthOnly => FLAG_ORIGINAL_LENGTH,
+            IntegrityMode::Crc32 => FLAG_ORIGINAL_LENGTH | FLAG_CRC32,
+        }
+    }
+}
+
+/// Argument positions holding the values of a built-in command, counted from the key at 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuePositions {
+    /// The command has no compressible arguments
+    None,
+    /// A single argument
+    At(usize),
+    /// Every argument from this position on
+    From(usize),
+    /// `key value [key value ...]`, each value paired with the key before it
+    KeyValuePairs,
+    /// `key field value [field value ...]`, the field names included when
+    /// `compress_hash_fields` is enabled
+    HashEntries,
+    /// `script numkeys [key ...] [arg ...]`, the ARGV positions listed in
+    /// `compress_eval_argv_indices`
+    ScriptArgv,
+    /// `key [options] id field value [field value ...]` as taken by XADD, the field values
+    StreamEntries,
+}
+
+/// Layout of the compressed values in the reply of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseShape {
+    /// The reply is returned as-is
+    None,
+    /// A single bulk string
+    SingleValue,
+    /// An array of bulk strings, nil for missing elements
+    Array,
+    /// A single member, or an array or set of members when a count is given, as replied by SPOP
+    Members,
+    /// `[key, element]` as replied by BLPOP and BRPOP, the key name is left as-is
+    KeyedValue,
+    /// `[key, [element ...]]` as replied by LMPOP
+    MultiPop,
+    /// `[key, [[member, score] ...]]` as replied by ZMPOP
+    ScoredMultiPop,
+    /// GEOSEARCH members, with or without the WITH* options
+    GeoMembers,
+    /// A field/value map, the field names included when `compress_hash_fields` is enabled
+    HashEntries,
+    /// An array of field names, only decompressed when `compress_hash_fields` is enabled
+    HashFields,
+    /// `[[id, [field, value, ...]], ...]` as replied by XRANGE, only the values are touched
+    StreamEntries,
+    /// `[[key, entries], ...]` as replied by XREAD, or a key to entries map in RESP3, with the
+    /// entries laid out as for `StreamEntries`
+    StreamsRead,
+}
+
+/// How a built-in command takes part in compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub request_type: RequestType,
+    pub behavior: CommandCompressionBehavior,
+    pub value_positions: ValuePositions,
+    pub response_shape: ResponseShape,
+}
+
+impl CommandSpec {
+    const fn compress(request_type: RequestType, value_positions: ValuePositions) -> Self {
+        Self {
+            request_type,
+            behavior: CommandCompressionBehavior::CompressValues,
+            value_positions,
+            response_shape: ResponseShape::None,
+        }
+    }
+
+    const fn decompress(request_type: RequestType, response_shape: ResponseShape) -> Self {
+        Self {
+            request_type,
+            behavior: CommandCompressionBehavior::DecompressValues,
+            value_positions: ValuePositions::None,
+            response_shape,
+        }
+    }
+}
+
+/// The built-in commands GLIDE compresses or decompresses. This single table drives
+/// [`RequestType::compression_behavior`], the argument positions compressed in requests and
+/// the decoding of replies; commands not listed are left alone.
+pub const COMMAND_SPECS: &[CommandSpec] = &[
+    // SET key value [options]; with the GET option the reply carries the previous value
+    CommandSpec {
+        response_shape: ResponseShape::SingleValue,
+        ..CommandSpec::compress(RequestType::Set, ValuePositions::At(1))
+    },
+    CommandSpec::compress(RequestType::HSet, ValuePositions::HashEntries),
+    // HSETNX key field value
+    CommandSpec::compress(RequestType::HSetNX, ValuePositions::At(2)),
+    // LSET key index element
+    CommandSpec::compress(RequestType::LSet, ValuePositions::At(2)),
+    // LINSERT key BEFORE|AFTER pivot element; the pivot is left as-is
+    CommandSpec::compress(RequestType::LInsert, ValuePositions::At(3)),
+    // LPUSH|RPUSH key element [element ...]
+    CommandSpec::compress(RequestType::LPush, ValuePositions::From(1)),
+    CommandSpec::compress(RequestType::RPush, ValuePositions::From(1)),
+    CommandSpec::compress(RequestType::MSet, ValuePositions::KeyValuePairs),
+    // Only the ARGV positions listed in `compress_eval_argv_indices`, none by default
+    CommandSpec::compress(RequestType::Eval, ValuePositions::ScriptArgv),
+    CommandSpec::compress(RequestType::EvalSha, ValuePositions::ScriptArgv),
+    CommandSpec::compress(RequestType::FCall, ValuePositions::ScriptArgv),
+    CommandSpec::decompress(RequestType::Get, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::GetDel, ResponseShape::SingleValue),
+    // MGET key [key ...], nil for missing keys
+    CommandSpec::decompress(RequestType::MGet, ResponseShape::Array),
+    CommandSpec::decompress(RequestType::LIndex, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::LRange, ResponseShape::Array),
+    // LPOP|RPOP key [count]
+    CommandSpec::decompress(RequestType::LPop, ResponseShape::Members),
+    CommandSpec::decompress(RequestType::RPop, ResponseShape::Members),
+    // The moved element, nil when the source list is empty
+    CommandSpec::decompress(RequestType::LMove, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::BLMove, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::RPopLPush, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::BRPopLPush, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::LMPop, ResponseShape::MultiPop),
+    CommandSpec::decompress(RequestType::BLMPop, ResponseShape::MultiPop),
+    // BLPOP|BRPOP key [key ...] timeout, nil when the timeout expires
+    CommandSpec::decompress(RequestType::BLPop, ResponseShape::KeyedValue),
+    CommandSpec::decompress(RequestType::BRPop, ResponseShape::KeyedValue),
+    CommandSpec::decompress(RequestType::ZMPop, ResponseShape::ScoredMultiPop),
+    CommandSpec::decompress(RequestType::GeoSearch, ResponseShape::GeoMembers),
+    CommandSpec::decompress(RequestType::HGet, ResponseShape::SingleValue),
+    CommandSpec::decompress(RequestType::HGetAll, ResponseShape::HashEntries),
+    // HMGET key field [field ...], nil for missing fields
+    CommandSpec::decompress(RequestType::HMGet, ResponseShape::Array),
+    // HRANDFIELD key count WITHVALUES; callers route its replies here only with WITHVALUES
+    CommandSpec::decompress(RequestType::HRandField, ResponseShape::HashEntries),
+    CommandSpec::decompress(RequestType::HVals, ResponseShape::Array),
+    // JSON.MGET replies with one JSON string per key, nil for missing keys
+    CommandSpec::decompress(RequestType::JsonMGet, ResponseShape::Array),
+    CommandSpec::decompress(RequestType::HKeys, ResponseShape::HashFields),
+    // SPOP key [count]
+    CommandSpec::decompress(RequestType::SPop, ResponseShape::Members),
+    CommandSpec::compress(RequestType::XAdd, ValuePositions::StreamEntries),
+    CommandSpec::decompress(RequestType::XRange, ResponseShape::StreamEntries),
+    CommandSpec::decompress(RequestType::XRevRange, ResponseShape::StreamEntries),
+    // XREAD|XREADGROUP ... STREAMS key [key ...] id [id ...], nil when nothing was read
+    CommandSpec::decompress(RequestType::XRead, ResponseShape::StreamsRead),
+    CommandSpec::decompress(RequestType::XReadGroup, ResponseShape::StreamsRead),
+];
+
+/// XADD field values at least this large are compressed with
+/// [`CompressionBackend::compress_stream`], bounding the memory used by the encoder
+pub const STREAM_COMPRESSION_THRESHOLD: usize = 1024 * 1024;
+
+/// Returns the entry of [`COMMAND_SPECS`] for `request_type`, if it is listed
+pub fn command_spec(request_type: RequestType) -> Option<&'static CommandSpec> {
+    COMMAND_SPECS
+        .iter()
+        .find(|spec| spec.request_type == request_type)
+}
+
+fn commands_with(behavior: CommandCompressionBehavior) -> Vec<RequestType> {
+    COMMAND_SPECS
+        .iter()
+        .filter(|spec| spec.behavior == behavior)
+        .map(|spec| spec.request_type)
+        .collect()
+}
+
+static COMPRESSING_COMMANDS: Lazy<Vec<RequestType>> =
+    Lazy::new(|| commands_with(CommandCompressionBehavior::CompressValues));
+static DECOMPRESSING_COMMANDS: Lazy<Vec<RequestType>> =
+    Lazy::new(|| commands_with(CommandCompressionBehavior::DecompressValues));
+
+/// Returns the commands whose values GLIDE compresses
+pub fn compressing_commands() -> &'static [RequestType] {
+    &COMPRESSING_COMMANDS
+}
+
+/// Returns the commands whose replies GLIDE decompresses
+pub fn decompressing_commands() -> &'static [RequestType] {
+    &DECOMPRESSING_COMMANDS
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommandCompressionBehavior {
+    CompressValues,
+    DecompressValues,
+    NoCompression,
+}
+
+impl CommandCompressionBehavior {
+    pub fn description(&self) -> &'static str {
+        match self {
+            CommandCompressionBehavior::CompressValues => {
+                "Compress values before sending to server"
+            }
+            CommandCompressionBehavior::DecompressValues => {
+                "Decompress values after receiving from server"
+            }
+            CommandCompressionBehavior::NoCompression => "No compression processing required",
+        }
+    }
+}
+
+impl std::fmt::Display for CommandCompressionBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandCompressionBehavior::CompressValues => write!(f, "CompressValues"),
+            CommandCompressionBehavior::DecompressValues => write!(f, "DecompressValues"),
+            CommandCompressionBehavior::NoCompression => write!(f, "NoCompression"),
+        }
+    }
+}
+
+/// Compression rules of a command that isn't known to GLIDE
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandEntry {
+    pub behavior: CommandCompressionBehavior,
+    /// Positions of the compressible values. Index 0 is the command name itself and index 1
+    /// the key; neither is ever compressed.
+    #[serde(rename = "indices", default)]
+    pub value_indices: Vec<usize>,
+}
+
+/// Compression classification for commands sent as `CustomCommand`, such as commands
+/// registered by Valkey modules. Command names are matched case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandCompressionTable {
+    entries: HashMap<Vec<u8>, CommandEntry>,
+}
+
+impl CommandCompressionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers (or replaces) the compression rules for a command name
+    pub fn register(
+        &mut self,
+        name: impl AsRef<[u8]>,
+        behavior: CommandCompressionBehavior,
+        value_indices: Vec<usize>,
+    ) {
+        self.entries.insert(
+            name.as_ref().to_ascii_uppercase(),
+            CommandEntry {
+                behavior,
+                value_indices,
+            },
+        );
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<&CommandEntry> {
+        self.entries.get(&name.to_ascii_uppercase())
+    }
+
+    pub fn contains(&self, name: &[u8]) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the behavior for a command name, `NoCompression` for unregistered commands
+    pub fn behavior(&self, name: &[u8]) -> CommandCompressionBehavior {
+        self.get(name)
+            .map(|entry| entry.behavior)
+            .unwrap_or(CommandCompressionBehavior::NoCompression)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl FromIterator<(Vec<u8>, CommandEntry)> for CommandCompressionTable {
+    fn from_iter<I: IntoIterator<Item = (Vec<u8>, CommandEntry)>>(iter: I) -> Self {
+        Self {
+            entries: iter
+                .into_iter()
+                .map(|(name, entry)| (name.to_ascii_uppercase(), entry))
+                .collect(),
+        }
+    }
+}
+
+/// Serialized as a map of command name to `{ "behavior": ..., "indices": [...] }`
+impl Serialize for CommandCompressionTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.entries
+                .iter()
+                .map(|(name, entry)| (String::from_utf8_lossy(name), entry)),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for CommandCompressionTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = HashMap::<String, CommandEntry>::deserialize(deserializer)?;
+        Ok(entries
+            .into_iter()
+            .map(|(name, entry)| (name.into_bytes(), entry))
+            .collect())
+    }
+}
+
+/// A value that [`CompressionManager::preflight`] expects to compress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedValue {
+    /// Position of the value in the analyzed arguments
+    pub index: usize,
+    pub input_size: usize,
+    /// Upper bound of the compressed size, including the header
+    pub max_output_size: usize,
+}
+
+/// Read-only analysis of the values a command would have compressed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompressionPlan {
+    pub values: Vec<PlannedValue>,
+}
+
+impl CompressionPlan {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Configuration of a [`CompressionManager`] as returned by [`CompressionManager::describe`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ManagerDescription {
+    pub backend: &'static str,
+    /// The configured compression level, or the backend default when none is set
+    pub level: Option<i32>,
+    pub enabled: bool,
+    pub min_size: usize,
+    pub max_size: Option<usize>,
+    /// Header version written for newly compressed values
+    pub header_version: u8,
+    /// See [`CompressionConfig::fingerprint`]
+    pub fingerprint: u32,
+}
+
+/// Point-in-time snapshot of a [`CompressionManager`]'s counters.
+/// The counters are always maintained, including while compression is disabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    pub values_compressed: u64,
+    pub values_skipped: u64,
+    /// Part of `values_skipped` skipped because compression is disabled, as opposed to
+    /// values below the thresholds or not worth compressing
+    pub values_skipped_disabled: u64,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    pub values_decompressed: u64,
+    pub decompressed_bytes: u64,
+}
+
+/// Projected effect of compression on a sample of values, see
+/// [`CompressionManager::estimate_savings`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SavingsReport {
+    /// Total size of the samples
+    pub total_in: usize,
+    /// Total size of the samples as they would be stored
+    pub total_out: usize,
+    pub compressed_count: usize,
+    pub skipped_count: usize,
+    /// Average compressed-to-original size ratio of the compressed samples,
+    /// 1.0 when no sample would be compressed
+    pub avg_ratio: f64,
+}
+
+/// Describes a decompression, see [`CompressionManager::decompress_value_with_meta`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecompressMeta {
+    /// Whether the input was compressed and has been decompressed
+    pub was_compressed: bool,
+    /// Size of the input as stored
+    pub compressed_size: usize,
+    /// Size of the returned value
+    pub decompressed_size: usize,
+    /// Backend that compressed the input, `None` for raw inputs
+    pub backend: Option<CompressionBackendType>,
+}
+
+/// Live per-manager counters backing [`CompressionStats`]. Updates hold the read side of
+/// `reset_lock` while a reset holds its write side, so a reset never splits an update.
+#[derive(Debug, Default)]
+struct StatsCounters {
+    reset_lock: std::sync::RwLock<()>,
+    values_compressed: AtomicU64,
+    values_skipped: AtomicU64,
+    values_skipped_disabled: AtomicU64,
+    original_bytes: AtomicU64,
+    compressed_bytes: AtomicU64,
+    values_decompressed: AtomicU64,
+    decompressed_bytes: AtomicU64,
+}
+
+impl StatsCounters {
+    fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            values_compressed: self.values_compressed.load(Ordering::Relaxed),
+            values_skipped: self.values_skipped.load(Ordering::Relaxed),
+            values_skipped_disabled: self.values_skipped_disabled.load(Ordering::Relaxed),
+            original_bytes: self.original_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+            values_decompressed: self.values_decompressed.load(Ordering::Relaxed),
+            decompressed_bytes: self.decompressed_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Applies an update touching one or more counters
+    fn update(&self, update: impl FnOnce(&Self)) {
+        let _guard = self.reset_lock.read().unwrap_or_else(|e| e.into_inner());
+        update(self);
+    }
+
+    fn reset(&self) -> CompressionStats {
+        let _guard = self.reset_lock.write().unwrap_or_else(|e| e.into_inner());
+        CompressionStats {
+            values_compressed: self.values_compressed.swap(0, Ordering::Relaxed),
+            values_skipped: self.values_skipped.swap(0, Ordering::Relaxed),
+            values_skipped_disabled: self.values_skipped_disabled.swap(0, Ordering::Relaxed),
+            original_bytes: self.original_bytes.swap(0, Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.swap(0, Ordering::Relaxed),
+            values_decompressed: self.values_decompressed.swap(0, Ordering::Relaxed),
+            decompressed_bytes: self.decompressed_bytes.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompressionManager {
+    backend: Box<dyn CompressionBackend>,
+    config: ArcSwap<CompressionConfig>,
+    command_table: CommandCompressionTable,
+    policy: Option<Box<dyn CompressionPolicy>>,
+    classifier: Option<Box<dyn ContentClassifier>>,
+    read_backends: Vec<Box<dyn CompressionBackend>>,
+    adaptive_threshold: Option<AdaptiveThreshold>,
+    stats: StatsCounters,
+    unknown_backend_ids: BackendIdSet,
+    #[cfg(feature = "cache")]
+    cache: Option<decompression_cache::DecompressionCache>,
+}
+
+/// Lock-free set of backend IDs, one bit per ID
+#[derive(Debug, Default)]
+struct BackendIdSet([AtomicU64; 4]);
+
+impl BackendIdSet {
+    fn insert(&self, id: u8) {
+        self.0[usize::from(id / 64)].fetch_or(1 << (id % 64), Ordering::Relaxed);
+    }
+
+    /// Returns the IDs in the set in ascending order
+    fn to_vec(&self) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|&id| {
+                self.0[usize::from(id / 64)].load(Ordering::Relaxed) & (1 << (id % 64)) != 0
+            })
+            .collect()
+    }
+}
+
+/// Exponential moving average of value sizes used by
+/// [`CompressionManager::with_adaptive_threshold`]
+#[derive(Debug)]
+struct AdaptiveThreshold {
+    factor: f64,
+    alpha: f64,
+    /// Bits of the `f64` average, zero until the first value is observed
+    average: AtomicU64,
+}
+
+impl AdaptiveThreshold {
+    /// Folds `size` into the average and returns whether it reaches the previous average
+    /// times the factor. The first value always does.
+    fn observe(&self, size: usize) -> bool {
+        let size = size as f64;
+        let previous = self
+            .average
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                let updated = if bits == 0 {
+                    size
+                } else {
+                    self.alpha * size + (1.0 - self.alpha) * f64::from_bits(bits)
+                };
+                Some(updated.to_bits())
+            })
+            .unwrap_or_else(|bits| bits);
+        size >= f64::from_bits(previous) * self.factor
+    }
+}
+
+/// Fluent builder for [`CompressionManager`].
+///
+/// The backend implementation is created from the selected [`CompressionBackendType`],
+/// so the backend and the configuration can never disagree.
+#[derive(Debug, Clone)]
+pub struct CompressionManagerBuilder {
+    backend: CompressionBackendType,
+    level: Option<i32>,
+    min_size: Option<usize>,
+    max_size: Option<usize>,
+}
+
+impl CompressionManagerBuilder {
+    pub fn new() -> Self {
+        Self {
+            backend: CompressionBackendType::Zstd,
+            level: None,
+            min_size: None,
+            max_size: None,
+        }
+    }
+
+    pub fn backend(mut self, backend: CompressionBackendType) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Sets the compression level. The backend's default level is used when not set.
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn min_size(mut self, size: usize) -> Self {
+        self.min_size = Some(size);
+        self
+    }
+
+    pub fn max_size(mut self, size: usize) -> Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    pub fn build(self) -> CompressionResult<CompressionManager> {
+        let mut config = CompressionConfig::new(self.backend);
+        if let Some(level) = self.level {
+            config = config.with_compression_level(Some(level));
+        }
+        if let Some(min_size) = self.min_size {
+            config = config.with_min_compression_size(min_size);
+        }
+        config = config.with_max_compression_size(self.max_size);
+
+        CompressionManager::new(self.backend.make_backend(), config)
+    }
+}
+
+impl Default for CompressionManagerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompressionManager {
+    pub fn new(
+        backend: Box<dyn CompressionBackend>,
+        config: CompressionConfig,
+    ) -> CompressionResult<Self> {
+        #[cfg(debug_assertions)]
+        assert_backend_consistency(backend.as_ref());
+
+        Self::validate_config_for(backend.as_ref(), &config)?;
+
+        Ok(Self::from_parts(backend, config))
+    }
+
+    /// Creates a manager with compression turned off, for pipelines that always carry one:
+    /// every `process_*` call leaves commands and replies untouched.
+    /// Compression can later be enabled with [`Self::swap_config`] using a zstd configuration.
+    pub fn new_disabled() -> Self {
+        // The disabled configuration names zstd, so the backend IDs match without validation
+        Self::from_parts(
+            CompressionBackendType::Zstd.make_backend(),
+            CompressionConfig::disabled(),
+        )
+    }
+
+    fn from_parts(backend: Box<dyn CompressionBackend>, config: CompressionConfig) -> Self {
+        Self {
+            backend,
+            #[cfg(feature = "cache")]
+            cache: config
+                .decompression_cache
+                .map(decompression_cache::DecompressionCache::new),
+            config: ArcSwap::from_pointee(config),
+            command_table: CommandCompressionTable::new(),
+            policy: None,
+            classifier: None,
+            read_backends: Vec::new(),
+            adaptive_threshold: None,
+            stats: StatsCounters::default(),
+            unknown_backend_ids: BackendIdSet::default(),
+        }
+    }
+
+    /// Sets the classification table consulted for `CustomCommand` requests
+    pub fn with_command_table(mut self, command_table: CommandCompressionTable) -> Self {
+        self.command_table = command_table;
+        self
+    }
+
+    pub fn command_table(&self) -> &CommandCompressionTable {
+        &self.command_table
+    }
+
+    /// Sets the policy deciding which command arguments are compressed, replacing the
+    /// size-based thresholds of the configuration
+    pub fn with_policy(mut self, policy: Box<dyn CompressionPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Sets the classifier asked whether each eligible value is worth compressing.
+    /// It is bypassed when `force_compress` is enabled.
+    pub fn with_classifier(mut self, classifier: Box<dyn ContentClassifier>) -> Self {
+        self.classifier = Some(classifier);
+        self
+    }
+
+    /// Sets additional backends used only to decompress values, matched by backend ID.
+    ///
+    /// Useful while migrating between backends: new values are written with the primary
+    /// backend while values written with the previous one remain readable.
+    pub fn with_read_backends(mut self, read_backends: Vec<Box<dyn CompressionBackend>>) -> Self {
+        self.read_backends = read_backends;
+        self
+    }
+
+    /// Compresses only values of at least `factor` times the exponential moving average of
+    /// the sizes seen so far, in addition to the thresholds of the configuration.
+    /// `alpha` is the weight of each new size in the average and is clamped to `0.0..=1.0`.
+    pub fn with_adaptive_threshold(mut self, factor: f32, alpha: f32) -> Self {
+        self.adaptive_threshold = Some(AdaptiveThreshold {
+            factor: f64::from(factor),
+            alpha: f64::from(alpha.clamp(0.0, 1.0)),
+            average: AtomicU64::new(0),
+        });
+        self
+    }
+
+    /// Returns the moving average of value sizes, or `None` without an adaptive threshold
+    pub fn adaptive_average(&self) -> Option<f64> {
+        self.adaptive_threshold
+            .as_ref()
+            .map(|threshold| f64::from_bits(threshold.average.load(Ordering::Relaxed)))
+    }
+
+    fn validate_config_for(
+        backend: &dyn CompressionBackend,
+        config: &CompressionConfig,
+    ) -> CompressionResult<()> {
+        Self::check_config_for(backend, config).map_err(|err| err.with_config(config))
+    }
+
+    fn check_config_for(
+        backend: &dyn CompressionBackend,
+        config: &CompressionConfig,
+    ) -> CompressionResult<()> {
+        config.validate_buildable()?;
+
+        if backend.backend_id() != config.backend.backend_id() {
+            return Err(CompressionError::invalid_configuration(
+                config.backend.backend_name(),
+                format!(
+                    "backend mismatch: expected {} (id: {}), got backend with id {}",
+                    config.backend.backend_name(),
+                    config.backend.backend_id(),
+                    backend.backend_id()
+                ),
+            ));
+        }
+
+        if let Some((request_type, dict_id)) = config
+            .command_dictionaries
+            .iter()
+            .find(|(_, dict_id)| backend.dictionary_epoch(**dict_id).is_none())
+        {
+            return Err(CompressionError::invalid_configuration(
+                config.backend.backend_name(),
+                format!(
+                    "dictionary {} used for {:?} is not registered with the backend",
+                    dict_id, request_type
                 ),
             ));
         }
 
-        // Validate compression level using backend-specific validation
-        backend.validate_compression_level(config.compression_level)?;
+        // Validate compression level using backend-specific validation
+        backend.validate_compression_level(config.compression_level)
+    }
+
+    /// Validates the new configuration and atomically replaces the current one.
+    ///
+    /// Operations already in progress finish with the configuration they started with.
+    /// The backend and the decompression cache bounds are fixed at construction, so the new
+    /// configuration must use the same backend.
+    pub fn swap_config(&self, config: CompressionConfig) -> CompressionResult<()> {
+        Self::validate_config_for(self.backend.as_ref(), &config)?;
+        self.config.store(Arc::new(config));
+        Ok(())
+    }
+
+    pub fn should_compress(&self, data: &[u8]) -> bool {
+        let config = self.config.load();
+        config.should_compress_value(data) && self.classifier_accepts(&config, data)
+    }
+
+    /// Read-side counterpart of [`Self::should_compress`]: returns whether `data`, replied to
+    /// `request_type`, may need decompressing. Compression must be enabled, the replies of
+    /// the command decompressed, and `data` long enough to hold a compressed value.
+    pub fn should_decompress(&self, data: &[u8], request_type: RequestType) -> bool {
+        self.decompresses_replies(request_type)
+            && self.config.load().strip_header_prefix(data).len() >= MIN_COMPRESSED_SIZE
+    }
+
+    /// Returns whether compression is enabled and the replies of `request_type` are decompressed
+    fn decompresses_replies(&self, request_type: RequestType) -> bool {
+        self.is_enabled()
+            && !request_type.never_decompresses()
+            && command_spec(request_type)
+                .is_some_and(|spec| spec.response_shape != ResponseShape::None)
+    }
+
+    /// Returns an upper bound of the compressed size of an `input_len` byte value, including
+    /// the header fields written by the current configuration
+    pub fn max_compressed_bound(&self, input_len: usize) -> usize {
+        let config = self.config.load();
+        let mut flags = config.header_flags();
+        if !config.command_dictionaries.is_empty() {
+            flags |= FLAG_DICTIONARY;
+        }
+        let frame_bound = |len: usize, flags: u8| {
+            let header_len = if flags == 0 {
+                HEADER_SIZE
+            } else {
+                header_size_for(flags)
+            };
+            self.backend.max_compressed_bound(len) - HEADER_SIZE + header_len
+        };
+        let bound = match config.chunking {
+            Some(chunking) if input_len > chunking.chunk_size => {
+                let flags = flags | FLAG_FRAME_LENGTH;
+                let remainder = input_len % chunking.chunk_size;
+                input_len / chunking.chunk_size * frame_bound(chunking.chunk_size, flags)
+                    + if remainder > 0 {
+                        frame_bound(remainder, flags)
+                    } else {
+                        0
+                    }
+            }
+            _ => frame_bound(input_len, flags),
+        };
+        config.header_prefix.len() + bound
+    }
+
+    /// Returns an upper bound of the size of `value` once processed by [`Self::compress_value`],
+    /// without compressing it: [`Self::max_compressed_bound`] when the value would be
+    /// compressed, and its own length otherwise
+    pub fn compressed_size_hint(&self, value: &[u8]) -> usize {
+        if self.should_compress(value) && !self.is_compressed(value) {
+            self.max_compressed_bound(value.len())
+        } else {
+            value.len()
+        }
+    }
+
+    /// Returns whether `data` was compressed by the configured backend, looking for the
+    /// header after the configured header prefix when present
+    pub fn is_compressed(&self, data: &[u8]) -> bool {
+        self.is_framed(&self.config.load(), data)
+    }
+
+    fn is_framed(&self, config: &CompressionConfig, data: &[u8]) -> bool {
+        self.backend.is_compressed(config.strip_header_prefix(data))
+    }
+
+    /// Describes which of the command arguments would be compressed, without modifying them.
+    ///
+    /// Indices refer to `args` as passed to [`process_command_args_for_compression`]. Values
+    /// that may still be sent raw after compression, because they don't shrink enough, are
+    /// listed as well.
+    pub fn preflight(&self, args: &[Vec<u8>], request_type: RequestType) -> CompressionPlan {
+        let config = self.config.load();
+        if !config.enabled {
+            return CompressionPlan::default();
+        }
+
+        let (offset, value_indices) = value_indices(args, request_type, self);
+        let values = value_indices
+            .into_iter()
+            // The key is never compressed
+            .filter(|&value_index| value_index != 0)
+            .filter_map(|value_index| {
+                let index = offset + value_index;
+                let value = args.get(index)?;
+                let key = args
+                    .get(offset + key_index(request_type, value_index))
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                (self.wants_compression(&config, key, value, request_type)
+                    && !self.is_framed(&config, value))
+                .then(|| PlannedValue {
+                    index,
+                    input_size: value.len(),
+                    max_output_size: self.max_compressed_bound(value.len()),
+                })
+            })
+            .collect();
+        CompressionPlan { values }
+    }
+
+    /// Returns a human-readable description of what this manager does for `request_type`,
+    /// for example `SET: compresses arg[1] with zstd level 3 for values 64..∞ bytes`
+    pub fn explain(&self, request_type: RequestType) -> String {
+        let name = format!("{:?}", request_type).to_uppercase();
+        let config = self.config.load();
+        if !config.enabled {
+            return format!("{}: compression disabled", name);
+        }
+        if matches!(request_type, RequestType::CustomCommand) {
+            return format!("{}: as registered in the command table", name);
+        }
+
+        match request_type.compression_behavior() {
+            CommandCompressionBehavior::CompressValues => {
+                let backend = match config
+                    .compression_level
+                    .or_else(|| self.backend.default_level())
+                {
+                    Some(level) => format!("{} level {}", self.backend.backend_name(), level),
+                    None => self.backend.backend_name().to_string(),
+                };
+                let eligible = if self.policy.is_some() {
+                    "values selected by the compression policy".to_string()
+                } else {
+                    let max = config
+                        .max_compression_size
+                        .map_or_else(|| "∞".to_string(), |max| max.to_string());
+                    format!("values {}..{} bytes", config.min_compression_size, max)
+                };
+                format!(
+                    "{}: compresses {} with {} for {}",
+                    name,
+                    describe_value_positions(request_type, &config),
+                    backend,
+                    eligible
+                )
+            }
+            CommandCompressionBehavior::DecompressValues => {
+                format!("{}: decompresses compressed values in the reply", name)
+            }
+            CommandCompressionBehavior::NoCompression => format!("{}: no compression", name),
+        }
+    }
+
+    /// Describes the current configuration in one serializable struct, for admin and
+    /// observability endpoints
+    pub fn describe(&self) -> ManagerDescription {
+        let config = self.config.load();
+        let header_version = if config.header_flags() == 0 && config.command_dictionaries.is_empty()
+        {
+            CURRENT_VERSION
+        } else {
+            FLAGS_VERSION
+        };
+        ManagerDescription {
+            backend: self.backend.backend_name(),
+            level: config
+                .compression_level
+                .or_else(|| self.backend.default_level()),
+            enabled: config.enabled,
+            min_size: config.min_compression_size,
+            max_size: config.max_compression_size,
+            header_version,
+            fingerprint: config.fingerprint(),
+        }
+    }
+
+    /// Returns a snapshot of this manager's counters
+    pub fn stats(&self) -> CompressionStats {
+        self.stats.snapshot()
+    }
+
+    /// Zeroes this manager's counters and returns their values from just before the reset.
+    /// The reset is atomic: a concurrent update is counted entirely before or after it.
+    pub fn reset_stats(&self) -> CompressionStats {
+        self.stats.reset()
+    }
+
+    fn record_compressed(&self, original_size: usize, compressed_size: usize) {
+        Telemetry::incr_total_values_compressed(1);
+        Telemetry::incr_total_original_bytes(original_size);
+        Telemetry::incr_total_bytes_compressed(compressed_size);
+        self.stats.update(|stats| {
+            stats.values_compressed.fetch_add(1, Ordering::Relaxed);
+            stats
+                .original_bytes
+                .fetch_add(original_size as u64, Ordering::Relaxed);
+            stats
+                .compressed_bytes
+                .fetch_add(compressed_size as u64, Ordering::Relaxed);
+        });
+    }
+
+    fn record_skipped(&self) {
+        Telemetry::incr_compression_skipped_count(1);
+        self.stats.update(|stats| {
+            stats.values_skipped.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    /// Records a value skipped because compression is disabled, which also counts as skipped
+    fn record_skipped_disabled(&self) {
+        Telemetry::incr_compression_skipped_count(1);
+        self.stats.update(|stats| {
+            stats.values_skipped.fetch_add(1, Ordering::Relaxed);
+            stats
+                .values_skipped_disabled
+                .fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    fn record_decompressed(&self, decompressed_size: usize) {
+        Telemetry::incr_total_values_decompressed(1);
+        Telemetry::incr_total_bytes_decompressed(decompressed_size);
+        self.stats.update(|stats| {
+            stats.values_decompressed.fetch_add(1, Ordering::Relaxed);
+            stats
+                .decompressed_bytes
+                .fetch_add(decompressed_size as u64, Ordering::Relaxed);
+        });
+    }
+
+    /// Attempts to compress the value with graceful fallback to original data
+    pub fn compress_value<'a>(&self, value: &'a [u8]) -> Cow<'a, [u8]> {
+        self.compress_value_if(value, None, |config| {
+            config.should_compress_value(value) && self.classifier_accepts(config, value)
+        })
+    }
+
+    /// Like [`Self::compress_value`] for a command argument, letting the configured
+    /// [`CompressionPolicy`] decide whether the value is compressed.
+    /// Stream entry values of at least [`STREAM_COMPRESSION_THRESHOLD`] bytes are compressed
+    /// with [`CompressionBackend::compress_stream`] to bound memory, and commands listed in
+    /// [`CompressionConfig::command_dictionaries`] use their dictionary.
+    pub fn compress_value_for<'a>(
+        &self,
+        key: &[u8],
+        value: &'a [u8],
+        request_type: RequestType,
+    ) -> Cow<'a, [u8]> {
+        self.compress_value_if(value, Some(request_type), |config| {
+            self.wants_compression(config, key, value, request_type)
+        })
+    }
+
+    /// Returns whether a command argument is eligible for compression, asking the policy
+    /// when one is set and using the size-based configuration otherwise
+    fn wants_compression(
+        &self,
+        config: &CompressionConfig,
+        key: &[u8],
+        value: &[u8],
+        request_type: RequestType,
+    ) -> bool {
+        let eligible = match &self.policy {
+            Some(policy) => {
+                config.enabled
+                    && !value.is_empty()
+                    && policy.should_compress(key, value, request_type)
+            }
+            None => config.should_compress_value(value),
+        };
+        eligible && self.classifier_accepts(config, value)
+    }
+
+    /// Returns whether the classifier, when set, considers the value worth compressing
+    fn classifier_accepts(&self, config: &CompressionConfig, value: &[u8]) -> bool {
+        config.force_compress
+            || self
+                .classifier
+                .as_ref()
+                .is_none_or(|classifier| classifier.is_worth_compressing(value))
+    }
+
+    fn compress_value_if<'a>(
+        &self,
+        value: &'a [u8],
+        request_type: Option<RequestType>,
+        eligible: impl FnOnce(&CompressionConfig) -> bool,
+    ) -> Cow<'a, [u8]> {
+        let config = self.config.load();
+        let stream = value.len() >= STREAM_COMPRESSION_THRESHOLD
+            && request_type
+                .and_then(command_spec)
+                .is_some_and(|spec| spec.value_positions == ValuePositions::StreamEntries);
+        // Values are written with the latest epoch of the dictionary
+        let dictionary = request_type
+            .and_then(|request_type| config.command_dictionaries.get(&request_type))
+            .map(|&dict_id| {
+                let epoch = self.backend.dictionary_epoch(dict_id).unwrap_or_default();
+                (dict_id, epoch)
+            });
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "compress_value",
+            backend = self.backend.backend_name(),
+            level = ?config.compression_level,
+            input_size = value.len(),
+            output_size = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let above_average = self
+            .adaptive_threshold
+            .as_ref()
+            .is_none_or(|threshold| threshold.observe(value.len()));
+        let result = if eligible(&config) && above_average {
+            self.compress_value_inner(&config, value, stream, dictionary)
+        } else {
+            if config.enabled {
+                self.record_skipped();
+            } else {
+                self.record_skipped_disabled();
+            }
+            Cow::Borrowed(value)
+        };
+
+        #[cfg(feature = "tracing")]
+        span.record("output_size", result.len());
+
+        result
+    }
+
+    fn compress_value_inner<'a>(
+        &self,
+        config: &CompressionConfig,
+        value: &'a [u8],
+        stream: bool,
+        dictionary: Option<(DictId, DictEpoch)>,
+    ) -> Cow<'a, [u8]> {
+        if self.is_framed(config, value) {
+            self.record_skipped();
+            return Cow::Borrowed(value);
+        }
+
+        match self.compress_framed(config, value, config.compression_level, stream, dictionary) {
+            Ok(compressed) => {
+                if config.should_keep_compressed(value.len(), compressed.len()) {
+                    // Successfully compressed and reduced size
+                    self.record_compressed(value.len(), compressed.len());
+                    Cow::Owned(compressed)
+                } else {
+                    // Compression didn't reduce size enough, skip it
+                    self.record_skipped();
+                    Cow::Borrowed(value)
+                }
+            }
+            Err(e) => {
+                self.record_skipped();
+                Self::report_fallback(config, &e);
+                Cow::Borrowed(value)
+            }
+        }
+    }
+
+    /// Like [`Self::compress_value`], also reporting whether the returned bytes were compressed
+    /// by this call. `false` means the original value is returned unchanged.
+    pub fn try_compress_value_reported(&self, value: &[u8]) -> (Vec<u8>, bool) {
+        match self.compress_value(value) {
+            Cow::Owned(compressed) => (compressed, true),
+            Cow::Borrowed(original) => (original.to_vec(), false),
+        }
+    }
+
+    /// Writes the compressed value into `out`, or a copy of the original when it isn't
+    /// compressed, and returns whether compression happened. `out` is cleared first and keeps
+    /// its capacity, so one scratch buffer can be reused across many values.
+    pub fn compress_into(&self, value: &[u8], out: &mut Vec<u8>) -> CompressionResult<bool> {
+        out.clear();
+        Ok(match self.compress_value(value) {
+            Cow::Owned(compressed) => {
+                out.extend_from_slice(&compressed);
+                true
+            }
+            Cow::Borrowed(original) => {
+                out.extend_from_slice(original);
+                false
+            }
+        })
+    }
+
+    /// Compresses a `Bytes` value. When the value isn't compressed, the returned `Bytes` shares
+    /// the input buffer; a new buffer is only allocated for compressed output.
+    #[cfg(feature = "bytes-api")]
+    pub fn compress_value_bytes(&self, value: &Bytes) -> CompressionResult<Bytes> {
+        Ok(match self.compress_value(value) {
+            Cow::Owned(compressed) => Bytes::from(compressed),
+            Cow::Borrowed(_) => value.clone(),
+        })
+    }
+
+    /// Decompresses a `Bytes` value. When no decompression is needed, the returned `Bytes`
+    /// shares the input buffer; a new buffer is only allocated for decompressed output.
+    #[cfg(feature = "bytes-api")]
+    pub fn decompress_value_bytes(&self, value: &Bytes) -> CompressionResult<Bytes> {
+        Ok(match self.decompress_value_cow(value)? {
+            Cow::Owned(decompressed) => Bytes::from(decompressed),
+            Cow::Borrowed(_) => value.clone(),
+        })
+    }
+
+    /// Compresses the value with an explicit level, overriding the configured level for this call only.
+    ///
+    /// Size gating and framing are the same as in [`Self::compress_value`]; the original data is
+    /// returned when the value is not eligible or compression doesn't reduce its size.
+    /// Unlike [`Self::compress_value`], an invalid level or a backend failure is reported as an error.
+    pub fn compress_value_with_level(
+        &self,
+        value: &[u8],
+        level: Option<i32>,
+    ) -> CompressionResult<Vec<u8>> {
+        self.backend.validate_compression_level(level)?;
+
+        let config = self.config.load();
+        if !config.should_compress_value(value)
+            || !self.classifier_accepts(&config, value)
+            || self.is_framed(&config, value)
+        {
+            self.record_skipped();
+            return Ok(value.to_vec());
+        }
+
+        let compressed = self.compress_framed(&config, value, level, false, None)?;
+        if config.should_keep_compressed(value.len(), compressed.len()) {
+            self.record_compressed(value.len(), compressed.len());
+            Ok(compressed)
+        } else {
+            self.record_skipped();
+            Ok(value.to_vec())
+        }
+    }
+
+    /// Compresses with the backend and adds the optional header fields required by the configuration.
+    ///
+    /// Every compression path goes through here, so this is where values already framed for
+    /// the configured backend are refused, whatever the thresholds or `force_compress` say.
+    /// Callers check first and skip such values; the error is only a backstop.
+    /// `stream` selects [`CompressionBackend::compress_stream`] unless a timeout is configured,
+    /// and `dictionary` selects [`CompressionBackend::compress_with_dictionary`] over both.
+    fn compress_framed(
+        &self,
+        config: &CompressionConfig,
+        value: &[u8],
+        level: Option<i32>,
+        stream: bool,
+        dictionary: Option<(DictId, DictEpoch)>,
+    ) -> CompressionResult<Vec<u8>> {
+        if self.is_framed(config, value) {
+            return Err(CompressionError::compression_failed(
+                self.backend.backend_name(),
+                level,
+                value.len(),
+                "value is already compressed",
+            ));
+        }
+
+        let framed = match config.chunking {
+            Some(chunking) if value.len() > chunking.chunk_size => {
+                let frames = parallel_map(
+                    &value.chunks(chunking.chunk_size).collect::<Vec<_>>(),
+                    chunking.parallel,
+                    |chunk| self.encode_frame(config, chunk, level, false, dictionary, true),
+                )?;
+                frames.concat()
+            }
+            _ => self.encode_frame(config, value, level, stream, dictionary, config.multi_frame)?,
+        };
+        Ok(with_header_prefix(config, framed))
+    }
+
+    /// Compresses `value` into a single frame, without the header prefix. `frame_length`
+    /// records the payload length so that further frames can follow.
+    fn encode_frame(
+        &self,
+        config: &CompressionConfig,
+        value: &[u8],
+        level: Option<i32>,
+        stream: bool,
+        dictionary: Option<(DictId, DictEpoch)>,
+        frame_length: bool,
+    ) -> CompressionResult<Vec<u8>> {
+        let compressed = match (dictionary, config.compress_timeout) {
+            (Some((dict_id, epoch)), _) => self
+                .backend
+                .compress_with_dictionary(value, level, dict_id, epoch)?,
+            (None, Some(timeout)) => {
+                self.backend
+                    .compress_with_deadline(value, level, Instant::now() + timeout)?
+            }
+            (None, None) if stream => {
+                let mut compressed = Vec::new();
+                self.backend
+                    .compress_stream(value, level, &mut compressed)?;
+                compressed
+            }
+            (None, None) => self.backend.compress(value, level)?,
+        };
+        if config.integrity_mode == IntegrityMode::None
+            && !config.record_level
+            && !frame_length
+            && dictionary.is_none()
+        {
+            return Ok(compressed);
+        }
 
-        Ok(Self { backend, config })
+        let mut header =
+            FrameHeader::with_integrity(self.backend.backend_id(), config.integrity_mode, value);
+        if config.record_level
+            && let Some(level) = level.or_else(|| self.backend.default_level())
+        {
+            header = header.with_level(level);
+        }
+        if frame_length {
+            header = header.with_frame_length(compressed.len() - HEADER_SIZE);
+        }
+        if let Some((dict_id, epoch)) = dictionary {
+            header = header.with_dictionary(dict_id, epoch);
+        }
+        let mut framed = header.to_bytes();
+        framed.extend_from_slice(&compressed[HEADER_SIZE..]);
+        Ok(framed)
     }
 
-    pub fn should_compress(&self, data: &[u8]) -> bool {
-        self.config.should_compress(data.len())
+    pub fn decompress_value(&self, value: &[u8]) -> CompressionResult<Vec<u8>> {
+        self.decompress_value_cow(value).map(Cow::into_owned)
     }
 
-    /// Attempts to compress the value with graceful fallback to original data
-    pub fn compress_value<'a>(&self, value: &'a [u8]) -> Cow<'a, [u8]> {
-        if !self.config.enabled || !self.should_compress(value) {
-            Telemetry::incr_compression_skipped_count(1);
-            return Cow::Borrowed(value);
+    /// Decompresses a value the caller knows was written by `expected`, for example from a
+    /// sidecar record. Fails with `UnsupportedBackend` when the header names another backend
+    /// instead of dispatching on it. Values without a compression header are returned as-is.
+    pub fn decompress_value_expecting(
+        &self,
+        data: &[u8],
+        expected: CompressionBackendType,
+    ) -> CompressionResult<Vec<u8>> {
+        let value = self.config.load().strip_header_prefix(data);
+        if let Some(backend_id) = extract_backend_id(value)
+            && is_glide_compressed(value)
+            && backend_id != expected.backend_id()
+        {
+            return Err(CompressionError::unsupported_backend(format!(
+                "backend ID 0x{:02x}, expected {}",
+                backend_id, expected
+            )));
         }
+        self.decompress_value(data)
+    }
 
-        if self.backend.is_compressed(value) {
-            Telemetry::incr_compression_skipped_count(1);
-            return Cow::Borrowed(value);
+    /// Decompresses the value and describes what was done, so callers don't need to inspect
+    /// the raw bytes themselves
+    pub fn decompress_value_with_meta(
+        &self,
+        data: &[u8],
+    ) -> CompressionResult<(Vec<u8>, DecompressMeta)> {
+        let decompressed = self.decompress_value_cow(data)?;
+        let was_compressed = matches!(decompressed, Cow::Owned(_));
+        let meta = DecompressMeta {
+            was_compressed,
+            compressed_size: data.len(),
+            decompressed_size: decompressed.len(),
+            backend: extract_backend_id(self.config.load().strip_header_prefix(data))
+                .filter(|_| was_compressed)
+                .and_then(CompressionBackendType::by_id),
+        };
+        Ok((decompressed.into_owned(), meta))
+    }
+
+    /// Decompresses the value without copying it when no decompression is needed.
+    ///
+    /// Returns `Cow::Borrowed` for uncompressed data or when compression is disabled,
+    /// and `Cow::Owned` only when the data was actually decompressed. Inputs shorter than
+    /// [`MIN_COMPRESSED_SIZE`] are returned without dispatching to any backend.
+    pub fn decompress_value_cow<'a>(&self, value: &'a [u8]) -> CompressionResult<Cow<'a, [u8]>> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "decompress_value",
+            backend = self.backend.backend_name(),
+            input_size = value.len(),
+            output_size = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let result = self.decompress_value_cow_inner(value);
+
+        #[cfg(feature = "tracing")]
+        if let Ok(ref decompressed) = result {
+            span.record("output_size", decompressed.len());
         }
 
-        match self.backend.compress(value, self.config.compression_level) {
-            Ok(compressed) => {
-                if compressed.len() < value.len() {
-                    // Successfully compressed and reduced size
-                    Telemetry::incr_total_values_compressed(1);
-                    Telemetry::incr_total_original_bytes(value.len());
-                    Telemetry::incr_total_bytes_compressed(compressed.len());
-                    Cow::Owned(compressed)
+        result
+    }
+
+    fn decompress_value_cow_inner<'a>(&self, data: &'a [u8]) -> CompressionResult<Cow<'a, [u8]>> {
+        let config = self.config.load();
+        if !config.enabled {
+            return Ok(Cow::Borrowed(data));
+        }
+
+        // Also skips inputs too short to be compressed, the common case of small replies
+        let value = config.strip_header_prefix(data);
+        if !is_glide_compressed(value) {
+            if config.try_headerless_backends
+                && let Some(decompressed) = self.decompress_headerless(value)
+            {
+                return Ok(Cow::Owned(decompressed));
+            }
+            return Ok(Cow::Borrowed(data));
+        }
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            if let Some(decompressed) = cache.get(value) {
+                // Strict mode may have been enabled since the entry was cached
+                if config.strict_decompress {
+                    self.check_strict_cached(value, &decompressed)?;
+                }
+                self.record_decompressed(decompressed.len());
+                return Ok(Cow::Owned(decompressed));
+            }
+            // Only values that passed the integrity and strict checks are cached
+            let decompressed = self.decompress_uncached(value)?;
+            if let Cow::Owned(ref decompressed) = decompressed {
+                cache.insert(value, decompressed);
+            }
+            return Ok(decompressed);
+        }
+
+        self.decompress_uncached(value)
+    }
+
+    fn decompress_uncached<'a>(&self, value: &'a [u8]) -> CompressionResult<Cow<'a, [u8]>> {
+        // Extract backend ID from header and route to appropriate backend
+        if let Some(backend_id) = extract_backend_id(value) {
+            let config = self.config.load();
+            let result = if config.strict_decompress {
+                // Each frame is checked against its own header, so values made of several
+                // frames are verified frame by frame
+                let frames = self.strict_frames(value)?;
+                let parallel = config.chunking.is_some_and(|chunking| chunking.parallel);
+                parallel_map(&frames, parallel, |(header, frame)| {
+                    let decompressed = self.decompress_routed(header.backend_id, frame)?;
+                    self.check_strict_output(frame, header, &decompressed)?;
+                    Ok(decompressed)
+                })
+                .map(|decompressed| decompressed.concat())
+            } else {
+                self.decompress_routed(backend_id, value)
+            };
+
+            // Update telemetry on successful decompression
+            if let Ok(ref decompressed) = result {
+                self.record_decompressed(decompressed.len());
+            }
+
+            result.map(Cow::Owned)
+        } else {
+            Ok(Cow::Borrowed(value))
+        }
+    }
+
+    /// Decompresses a value with the backend its header names
+    fn decompress_routed(&self, backend_id: u8, value: &[u8]) -> CompressionResult<Vec<u8>> {
+        // If the data was compressed with our configured backend, use it
+        // This respects the client's compression configuration
+        if backend_id == self.backend.backend_id() {
+            return self.decompress_with_backend(value);
+        }
+        if let Some(backend) = self
+            .read_backends
+            .iter()
+            .find(|backend| backend.backend_id() == backend_id)
+        {
+            return backend.decompress(value);
+        }
+        // Otherwise, use a static backend for decompression
+        // Static backends are shared and don't allocate on each call
+        // Return error if backend is not supported
+        let backend = get_backend_for_decompression(backend_id).inspect_err(|_| {
+            self.unknown_backend_ids.insert(backend_id);
+        })?;
+        backend.decompress(value)
+    }
+
+    /// Tries the raw decoder of the configured backend, then of the read backends, in order,
+    /// returning the first successful output
+    fn decompress_headerless(&self, value: &[u8]) -> Option<Vec<u8>> {
+        let decompressed = std::iter::once(&self.backend)
+            .chain(&self.read_backends)
+            .find_map(|backend| backend.decompress_raw(value).ok())?;
+        self.record_decompressed(decompressed.len());
+        Some(decompressed)
+    }
+
+    /// Decompresses with the configured backend. With parallel chunking configured, the
+    /// frames of values made of several frames are decompressed on several threads.
+    fn decompress_with_backend(&self, value: &[u8]) -> CompressionResult<Vec<u8>> {
+        if !self
+            .config
+            .load()
+            .chunking
+            .is_some_and(|chunking| chunking.parallel)
+        {
+            return self.backend.decompress(value);
+        }
+
+        let mut frames = Vec::new();
+        let mut remaining = value;
+        while let Some((_, _, rest)) = next_frame(remaining) {
+            frames.push(&remaining[..remaining.len() - rest.len()]);
+            remaining = rest;
+            if rest.is_empty() {
+                break;
+            }
+        }
+        // Single frames and malformed values are left to the backend, which reports errors
+        if frames.len() < 2 || !remaining.is_empty() {
+            return self.backend.decompress(value);
+        }
+        parallel_map(&frames, true, |frame| self.backend.decompress(frame))
+            .map(|decompressed| decompressed.concat())
+    }
+
+    /// Splits a value decompressed in strict mode into its frames, each of which must declare
+    /// its original length. Returns the header and the encoded bytes of every frame.
+    fn strict_frames<'a>(
+        &self,
+        value: &'a [u8],
+    ) -> CompressionResult<Vec<(FrameHeader, &'a [u8])>> {
+        let mut frames = Vec::new();
+        let mut remaining = value;
+        while !remaining.is_empty() {
+            let Some((header, _, rest)) = next_frame(remaining) else {
+                return Err(self.strict_error(value, "malformed frame".to_string()));
+            };
+            if header.original_length.is_none() {
+                return Err(self.strict_error(
+                    value,
+                    "strict mode requires a declared original length".to_string(),
+                ));
+            }
+            frames.push((header, &remaining[..remaining.len() - rest.len()]));
+            remaining = rest;
+        }
+        Ok(frames)
+    }
+
+    /// Checks the output of a strict decompression of one frame against its header,
+    /// independently of the backend, and rejects output that is itself still compressed
+    fn check_strict_output(
+        &self,
+        frame: &[u8],
+        header: &FrameHeader,
+        decompressed: &[u8],
+    ) -> CompressionResult<()> {
+        match header.verify(decompressed) {
+            Err(reason) => Err(self.strict_error(frame, reason)),
+            Ok(()) if is_glide_compressed(decompressed) => {
+                Err(self.strict_error(frame, "decompressed value is still compressed".to_string()))
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Checks a cached decompression in strict mode, splitting it at the lengths the frames
+    /// of the value declare
+    #[cfg(feature = "cache")]
+    fn check_strict_cached(&self, value: &[u8], decompressed: &[u8]) -> CompressionResult<()> {
+        let mut remaining = decompressed;
+        for (header, frame) in self.strict_frames(value)? {
+            let length = header.original_length.unwrap_or_default() as usize;
+            let (output, rest) = remaining.split_at(length.min(remaining.len()));
+            self.check_strict_output(frame, &header, output)?;
+            remaining = rest;
+        }
+        if !remaining.is_empty() {
+            return Err(self.strict_error(
+                value,
+                format!("{} trailing decompressed bytes", remaining.len()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn strict_error(&self, value: &[u8], reason: String) -> CompressionError {
+        CompressionError::decompression_failed(self.backend.backend_name(), value.len(), reason)
+    }
+
+    /// Returns the distinct backend IDs, in ascending order, of the values this manager failed
+    /// to decompress because no backend supports them. Non-empty results usually mean values
+    /// were written by a newer client, which operators may want to alert on.
+    pub fn unknown_backend_ids(&self) -> Vec<u8> {
+        self.unknown_backend_ids.to_vec()
+    }
+
+    /// Returns the current configuration
+    pub fn config(&self) -> Arc<CompressionConfig> {
+        self.config.load_full()
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.backend_name()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.load().enabled
+    }
+
+    /// Returns the counters of the decompression cache, or `None` when the cache is not configured
+    #[cfg(feature = "cache")]
+    pub fn cache_stats(&self) -> Option<DecompressionCacheStats> {
+        self.cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Attempts to decompress the value with graceful fallback to original data
+    pub fn try_decompress_value(&self, value: &[u8]) -> Vec<u8> {
+        self.try_decompress_value_cow(value).into_owned()
+    }
+
+    /// Like [`Self::decompress_value_cow`], falling back to the original data on error
+    fn try_decompress_value_cow<'a>(&self, value: &'a [u8]) -> Cow<'a, [u8]> {
+        self.decompress_value_cow(value).unwrap_or_else(|e| {
+            Self::report_fallback(&self.config.load(), &e);
+            Cow::Borrowed(value)
+        })
+    }
+
+    fn report_fallback(config: &CompressionConfig, error: &CompressionError) {
+        if let Some(hook) = &config.on_fallback {
+            hook.call(error);
+        }
+    }
+
+    /// Estimates the effect of compression on `samples`, applying the same thresholds as
+    /// [`Self::compress_value`]. Counters and fallback hooks are left untouched.
+    pub fn estimate_savings(&self, samples: &[Vec<u8>]) -> SavingsReport {
+        let config = self.config.load();
+        let mut report = SavingsReport {
+            total_in: 0,
+            total_out: 0,
+            compressed_count: 0,
+            skipped_count: 0,
+            avg_ratio: 1.0,
+        };
+        let mut ratio_sum = 0.0;
+
+        for sample in samples {
+            let compressed_size = (config.should_compress_value(sample)
+                && self.classifier_accepts(&config, sample)
+                && !self.is_framed(&config, sample))
+            .then(|| self.compress_framed(&config, sample, config.compression_level, false, None))
+            .and_then(Result::ok)
+            .map(|compressed| compressed.len())
+            .filter(|&size| config.should_keep_compressed(sample.len(), size));
+
+            report.total_in += sample.len();
+            match compressed_size {
+                Some(size) => {
+                    report.total_out += size;
+                    report.compressed_count += 1;
+                    ratio_sum += size as f64 / sample.len() as f64;
+                }
+                None => {
+                    report.total_out += sample.len();
+                    report.skipped_count += 1;
+                }
+            }
+        }
+
+        if report.compressed_count > 0 {
+            report.avg_ratio = ratio_sum / report.compressed_count as f64;
+        }
+        report
+    }
+
+    /// Read-modify-write helper for append-like updates, which APPEND and SETRANGE can't
+    /// perform on compressed values: decompresses `existing`, appends `appended` and compresses
+    /// the result. The returned bytes are meant to be written back with SET.
+    /// Uncompressed `existing` values are appended to as-is.
+    pub fn prepare_append(&self, existing: &[u8], appended: &[u8]) -> CompressionResult<Vec<u8>> {
+        let mut combined = self.decompress_value_cow(existing)?.into_owned();
+        combined.extend_from_slice(appended);
+        Ok(self.compress_value(&combined).into_owned())
+    }
+
+    /// Recovery helper: decompresses the value, or on failure returns the payload after the
+    /// header so it can be inspected or re-processed externally. Never fails.
+    /// Data in another format is returned as-is, and decoding failures are still reported to
+    /// the fallback hook.
+    pub fn decompress_or_strip(&self, data: &[u8]) -> Vec<u8> {
+        match self.decompress_value_cow(data) {
+            Ok(decompressed) => decompressed.into_owned(),
+            Err(e) => {
+                let config = self.config.load();
+                Self::report_fallback(&config, &e);
+                strip_header(config.strip_header_prefix(data)).to_vec()
+            }
+        }
+    }
+
+    /// Alias of [`Self::decompress_or_strip`], for callers that never want to see
+    /// "GLID"-prefixed data
+    #[inline]
+    pub fn decompress_value_or_raw(&self, data: &[u8]) -> Vec<u8> {
+        self.decompress_or_strip(data)
+    }
+
+    /// Decompresses each value with graceful fallback, preserving order
+    pub fn decompress_all(&self, values: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        let config = self.config.load();
+        values
+            .into_iter()
+            .map(|value| {
+                if is_glide_compressed(config.strip_header_prefix(&value)) {
+                    self.try_decompress_value(&value)
                 } else {
-                    // Compression didn't reduce size, skip it
-                    Telemetry::incr_compression_skipped_count(1);
-                    Cow::Borrowed(value)
+                    value
+                }
+            })
+            .collect()
+    }
+}
+
+/// Counters of the decompression cache
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecompressionCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// LRU cache of decompressed values keyed by the compressed bytes.
+///
+/// Compressed bytes uniquely determine the decompressed output, so entries never need invalidation.
+/// The full compressed bytes are kept and compared on lookup, since a digest collision would
+/// return the plaintext of another value.
+#[cfg(feature = "cache")]
+mod decompression_cache {
+    use super::*;
+    use lru::LruCache;
+    use std::num::NonZeroUsize;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct CacheState {
+        entries: LruCache<Vec<u8>, Vec<u8>>,
+        bytes: usize,
+        hits: u64,
+        misses: u64,
+    }
+
+    #[derive(Debug)]
+    pub(super) struct DecompressionCache {
+        max_bytes: usize,
+        state: Mutex<CacheState>,
+    }
+
+    impl DecompressionCache {
+        pub(super) fn new(config: DecompressionCacheConfig) -> Self {
+            let max_entries = NonZeroUsize::new(config.max_entries).unwrap_or(NonZeroUsize::MIN);
+            Self {
+                max_bytes: config.max_bytes,
+                state: Mutex::new(CacheState {
+                    entries: LruCache::new(max_entries),
+                    bytes: 0,
+                    hits: 0,
+                    misses: 0,
+                }),
+            }
+        }
+
+        pub(super) fn get(&self, compressed: &[u8]) -> Option<Vec<u8>> {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            match state.entries.get(compressed).cloned() {
+                Some(decompressed) => {
+                    state.hits += 1;
+                    Some(decompressed)
+                }
+                None => {
+                    state.misses += 1;
+                    None
                 }
             }
-            Err(_) => {
-                Telemetry::incr_compression_skipped_count(1);
-                Cow::Borrowed(value)
+        }
+
+        pub(super) fn insert(&self, compressed: &[u8], decompressed: &[u8]) {
+            let size = compressed.len() + decompressed.len();
+            if size > self.max_bytes {
+                return;
+            }
+
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.bytes += size;
+            if let Some((key, evicted)) = state
+                .entries
+                .push(compressed.to_vec(), decompressed.to_vec())
+            {
+                state.bytes -= key.len() + evicted.len();
+            }
+            while state.bytes > self.max_bytes {
+                match state.entries.pop_lru() {
+                    Some((key, evicted)) => state.bytes -= key.len() + evicted.len(),
+                    None => break,
+                }
+            }
+        }
+
+        pub(super) fn stats(&self) -> DecompressionCacheStats {
+            let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            DecompressionCacheStats {
+                entries: state.entries.len(),
+                bytes: state.bytes,
+                hits: state.hits,
+                misses: state.misses,
             }
         }
     }
+}
 
-    pub fn decompress_value(&self, value: &[u8]) -> CompressionResult<Vec<u8>> {
-        if !self.config.enabled {
-            return Ok(value.to_vec());
+#[cfg(any(feature = "zstd", feature = "wasm"))]
+pub mod zstd_backend {
+    use super::*;
+
+    /// Size of the chunks fed to the streaming encoder between deadline checks
+    #[cfg(not(feature = "wasm"))]
+    const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Upper bound of the output buffer preallocated from the content size declared by a
+    /// frame, which comes from untrusted data
+    #[cfg(not(feature = "wasm"))]
+    const MAX_PREALLOCATED_SIZE: u64 = 16 * 1024 * 1024;
+
+    #[cfg(not(feature = "wasm"))]
+    fn level_range() -> std::ops::RangeInclusive<i32> {
+        zstd::compression_level_range()
+    }
+
+    /// The levels accepted by libzstd, so configurations stay portable, although the
+    /// pure-Rust encoder always encodes at its fastest level
+    #[cfg(feature = "wasm")]
+    fn level_range() -> std::ops::RangeInclusive<i32> {
+        -(1 << 17)..=22
+    }
+
+    /// Returns the version number and the version string of the linked zstd library
+    #[cfg(not(feature = "wasm"))]
+    fn library_version() -> (u32, &'static str) {
+        (
+            zstd::zstd_safe::version_number(),
+            zstd::zstd_safe::version_string(),
+        )
+    }
+
+    /// No zstd library is linked with the pure-Rust codec
+    #[cfg(feature = "wasm")]
+    fn library_version() -> (u32, &'static str) {
+        (0, "none (pure-Rust codec)")
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn compress_bound(input_len: usize) -> usize {
+        zstd::zstd_safe::compress_bound(input_len)
+    }
+
+    /// `ZSTD_COMPRESSBOUND` of the reference implementation, which the pure-Rust encoder
+    /// stays within since it stores incompressible blocks raw
+    #[cfg(feature = "wasm")]
+    fn compress_bound(input_len: usize) -> usize {
+        const SMALL_INPUT: usize = 128 * 1024;
+        input_len
+            + (input_len >> 8)
+            + if input_len < SMALL_INPUT {
+                (SMALL_INPUT - input_len) >> 11
+            } else {
+                0
+            }
+    }
+
+    /// Encodes `data` as a single zstd frame recording the content size, which lets decoders
+    /// allocate their output once
+    #[cfg(not(feature = "wasm"))]
+    fn encode_payload(
+        data: &[u8],
+        level: i32,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, String> {
+        match dictionary {
+            Some(dictionary) => zstd::bulk::Compressor::with_dictionary(level, dictionary),
+            None => zstd::bulk::Compressor::new(level),
+        }
+        .and_then(|mut compressor| {
+            compressor.include_contentsize(true)?;
+            compressor.compress(data)
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    /// Encodes `data` as a single zstd frame with the pure-Rust encoder, which only implements
+    /// its fastest level and no dictionaries
+    #[cfg(feature = "wasm")]
+    fn encode_payload(
+        data: &[u8],
+        _level: i32,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Vec<u8>, String> {
+        if dictionary.is_some() {
+            return Err("dictionaries are not supported by the pure-Rust codec".to_string());
+        }
+        Ok(ruzstd::encoding::compress_to_vec(
+            data,
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ))
+    }
+
+    /// Returns a reader streaming the decoded zstd payload
+    #[cfg(not(feature = "wasm"))]
+    fn payload_reader(payload: &[u8]) -> Result<impl std::io::Read + '_, String> {
+        zstd::stream::read::Decoder::with_buffer(payload).map_err(|e| e.to_string())
+    }
+
+    /// Returns a reader streaming the decoded zstd payload, using the pure-Rust decoder.
+    /// The framing is identical, so values written by native clients remain readable.
+    #[cfg(feature = "wasm")]
+    fn payload_reader(payload: &[u8]) -> Result<impl std::io::Read + '_, String> {
+        ruzstd::decoding::StreamingDecoder::new(payload).map_err(|e| e.to_string())
+    }
+
+    /// Returns a reader streaming the zstd payload decoded with `dictionary`
+    #[cfg(not(feature = "wasm"))]
+    fn dictionary_payload_reader<'a>(
+        payload: &'a [u8],
+        dictionary: &[u8],
+    ) -> Result<impl std::io::Read + 'a, String> {
+        zstd::stream::read::Decoder::with_dictionary(payload, dictionary).map_err(|e| e.to_string())
+    }
+
+    /// The pure-Rust decoder only accepts trained dictionaries, so values written with a
+    /// dictionary can't be read
+    #[cfg(feature = "wasm")]
+    fn dictionary_payload_reader<'a>(
+        _payload: &'a [u8],
+        _dictionary: &[u8],
+    ) -> Result<impl std::io::Read + 'a, String> {
+        Err::<std::io::Empty, _>(
+            "dictionaries are not supported by the pure-Rust codec".to_string(),
+        )
+    }
+
+    /// Returns the output size to preallocate from the content size the encoder records in
+    /// the zstd frame header, when present
+    #[cfg(not(feature = "wasm"))]
+    fn preallocated_size(payload: &[u8]) -> usize {
+        zstd::zstd_safe::get_frame_content_size(payload)
+            .ok()
+            .flatten()
+            .map_or(0, |size| size.min(MAX_PREALLOCATED_SIZE) as usize)
+    }
+
+    /// The pure-Rust decoder grows its output as it goes
+    #[cfg(feature = "wasm")]
+    fn preallocated_size(_payload: &[u8]) -> usize {
+        0
+    }
+
+    /// Decodes the zstd payload following the header, allocating the output buffer once when
+    /// the frame declares its content size
+    fn decode_payload(payload: &[u8], dictionary: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        use std::io::Read;
+
+        let mut decoded = Vec::with_capacity(preallocated_size(payload));
+        match dictionary {
+            Some(dictionary) => {
+                dictionary_payload_reader(payload, dictionary)?.read_to_end(&mut decoded)
+            }
+            None => payload_reader(payload)?.read_to_end(&mut decoded),
+        }
+        .map_err(|e| e.to_string())?;
+        Ok(decoded)
+    }
+
+    /// Reads the decoded payload through a fixed-size buffer, returning its length and CRC32
+    fn digest_payload(mut reader: impl std::io::Read) -> Result<(usize, u32), String> {
+        let mut hasher = crc32fast::Hasher::new();
+        let mut length = 0;
+        let mut buffer = [0u8; 8 * 1024];
+        loop {
+            let read = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+            if read == 0 {
+                return Ok((length, hasher.finalize()));
+            }
+            hasher.update(&buffer[..read]);
+            length += read;
+        }
+    }
+
+    pub struct ZstdBackend {
+        default_level: i32,
+        dictionaries: HashMap<(DictId, DictEpoch), Vec<u8>>,
+        version: u32,
+    }
+
+    impl ZstdBackend {
+        pub fn new() -> Self {
+            Self {
+                default_level: 3,
+                dictionaries: HashMap::new(),
+                version: library_version().0,
+            }
+        }
+
+        /// Returns the version number of the linked zstd library, recorded at construction,
+        /// e.g. 10507 for 1.5.7. Always 0 with the pure-Rust codec of the `wasm` feature.
+        pub fn version(&self) -> u32 {
+            self.version
+        }
+
+        /// Round-trips a sample value through the encoder and the decoder, failing with
+        /// `BackendInitializationFailed` naming the linked zstd version when the output
+        /// doesn't match. Meant to be called once at startup, since a library that doesn't
+        /// match the bindings may otherwise only fail on some frames.
+        pub fn health_check(&self) -> CompressionResult<()> {
+            let sample = b"glide zstd self-test ".repeat(16);
+            let result = self
+                .compress(&sample, None)
+                .and_then(|compressed| self.decompress(&compressed));
+            let reason = match result {
+                Ok(decompressed) if decompressed == sample => return Ok(()),
+                Ok(_) => "self-test round trip returned different data".to_string(),
+                Err(e) => format!("self-test round trip failed: {}", e),
+            };
+            Err(CompressionError::backend_initialization_failed(
+                self.backend_name(),
+                format!(
+                    "{} (linked zstd {}, version number {})",
+                    reason,
+                    library_version().1,
+                    self.version
+                ),
+            ))
         }
 
-        if !has_magic_header(value) {
-            return Ok(value.to_vec());
+        /// Registers a pre-shared dictionary under `dict_id` at epoch 0, either raw content or
+        /// a dictionary trained with zstd. Values written with it can only be read by backends
+        /// holding the same dictionary under the same id and epoch.
+        pub fn with_dictionary(self, dict_id: DictId, dictionary: impl Into<Vec<u8>>) -> Self {
+            self.with_dictionary_epoch(dict_id, 0, dictionary)
         }
 
-        // Extract backend ID from header and route to appropriate backend
-        if let Some(backend_id) = extract_backend_id(value) {
-            // If the data was compressed with our configured backend, use it
-            // This respects the client's compression configuration
-            let result = if backend_id == self.backend.backend_id() {
-                self.backend.decompress(value)
-            } else {
-                // Otherwise, use a static backend for decompression
-                // Static backends are shared and don't allocate on each call
-                // Return error if backend is not supported
-                let backend = get_backend_for_decompression(backend_id)?;
-                backend.decompress(value)
-            };
-
-            // Update telemetry on successful decompression
-            if let Ok(ref decompressed) = result {
-                Telemetry::incr_total_values_decompressed(1);
-                Telemetry::incr_total_bytes_decompressed(decompressed.len());
-            }
+        /// Registers a retrained dictionary under `dict_id` and `epoch`. New values use the
+        /// highest epoch registered for the id; keep the previous epochs registered as long
+        /// as values written with them may be read.
+        pub fn with_dictionary_epoch(
+            mut self,
+            dict_id: DictId,
+            epoch: DictEpoch,
+            dictionary: impl Into<Vec<u8>>,
+        ) -> Self {
+            self.dictionaries
+                .insert((dict_id, epoch), dictionary.into());
+            self
+        }
 
-            result
-        } else {
-            Ok(value.to_vec())
+        fn dictionary(&self, dict_id: DictId, epoch: DictEpoch) -> Result<&[u8], String> {
+            self.dictionaries
+                .get(&(dict_id, epoch))
+                .map(Vec::as_slice)
+                .ok_or_else(|| format!("dictionary {} epoch {} is not registered", dict_id, epoch))
         }
-    }
 
-    pub fn config(&self) -> &CompressionConfig {
-        &self.config
+        /// Returns the dictionary named by the header, if any
+        fn frame_dictionary(&self, header: &FrameHeader) -> Result<Option<&[u8]>, String> {
+            header
+                .dictionary_id
+                .map(|dict_id| self.dictionary(dict_id, header.dictionary_epoch))
+                .transpose()
+        }
     }
 
-    pub fn backend_name(&self) -> &'static str {
-        self.backend.backend_name()
+    impl fmt::Debug for ZstdBackend {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut dictionaries: Vec<_> = self.dictionaries.keys().collect();
+            dictionaries.sort();
+            f.debug_struct("ZstdBackend")
+                .field("default_level", &self.default_level)
+                .field("dictionaries", &dictionaries)
+                .field("version", &self.version)
+                .finish()
+        }
     }
 
-    pub fn is_enabled(&self) -> bool {
-        self.config.enabled
+    impl Default for ZstdBackend {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
-    /// Attempts to decompress the value with graceful fallback to original data
-    pub fn try_decompress_value(&self, value: &[u8]) -> Vec<u8> {
-        self.decompress_value(value)
-            .unwrap_or_else(|_| value.to_vec())
-    }
-}
+    impl ZstdBackend {
+        /// Writes the header and the zstd frame of `data` into `output`, feeding the streaming
+        /// encoder in chunks and checking `deadline` after each of them
+        #[cfg(not(feature = "wasm"))]
+        fn encode_stream(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            deadline: Option<Instant>,
+            output: &mut Vec<u8>,
+        ) -> CompressionResult<()> {
+            use std::io::Write;
 
-pub mod zstd_backend {
-    use super::*;
+            let compression_level = level.unwrap_or(self.default_level);
+            self.validate_compression_level(Some(compression_level))?;
 
-    #[derive(Debug)]
-    pub struct ZstdBackend {
-        default_level: i32,
-    }
+            output.clear();
+            output.extend_from_slice(&create_header(self.backend_id()));
+            let mut encoder = zstd::stream::write::Encoder::new(output, compression_level)?;
+            encoder.set_pledged_src_size(Some(data.len() as u64))?;
+            encoder.include_contentsize(true)?;
+            for chunk in data.chunks(STREAM_CHUNK_SIZE) {
+                encoder.write_all(chunk)?;
+                if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                    return Err(CompressionError::compression_failed(
+                        self.backend_name(),
+                        Some(compression_level),
+                        data.len(),
+                        "compression timeout",
+                    ));
+                }
+            }
+            encoder.finish()?;
+            Ok(())
+        }
 
-    impl ZstdBackend {
-        pub fn new() -> Self {
-            Self { default_level: 3 }
+        /// Writes the header and the zstd frame of `data` into `output`. The pure-Rust encoder
+        /// has no streaming API, so `deadline` is only checked once the frame is encoded.
+        #[cfg(feature = "wasm")]
+        fn encode_stream(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            deadline: Option<Instant>,
+            output: &mut Vec<u8>,
+        ) -> CompressionResult<()> {
+            let compression_level = level.unwrap_or(self.default_level);
+            let compressed = self.compress(data, Some(compression_level))?;
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                return Err(CompressionError::compression_failed(
+                    self.backend_name(),
+                    Some(compression_level),
+                    data.len(),
+                    "compression timeout",
+                ));
+            }
+            output.clear();
+            output.extend_from_slice(&compressed);
+            Ok(())
         }
-    }
 
-    impl Default for ZstdBackend {
-        fn default() -> Self {
-            Self::new()
+        /// Calls `visit` with the header and payload of each frame of `data`.
+        /// Frames carrying their length may be followed by further frames.
+        fn for_each_frame(
+            &self,
+            data: &[u8],
+            mut visit: impl FnMut(&FrameHeader, &[u8]) -> Result<(), String>,
+        ) -> CompressionResult<()> {
+            let failed = |reason: String| {
+                CompressionError::decompression_failed(self.backend_name(), data.len(), reason)
+            };
+
+            if !self.is_compressed(data) {
+                return Err(failed(
+                    "data is not compressed or has invalid header".to_string(),
+                ));
+            }
+
+            let mut remaining = data;
+            loop {
+                check_header_version(remaining).map_err(failed)?;
+                let (header, payload, rest) = next_frame(remaining)
+                    .ok_or_else(|| failed("truncated compression header".to_string()))?;
+                visit(&header, payload).map_err(failed)?;
+
+                if rest.is_empty() {
+                    return Ok(());
+                }
+                if !self.is_compressed(rest) {
+                    return Err(failed("trailing data after compressed frame".to_string()));
+                }
+                remaining = rest;
+            }
         }
     }
 
@@ -454,14 +3489,15 @@ pub mod zstd_backend {
 
             self.validate_compression_level(Some(compression_level))?;
 
-            let compressed_data = zstd::encode_all(data, compression_level).map_err(|e| {
-                CompressionError::compression_failed(
-                    self.backend_name(),
-                    Some(compression_level),
-                    data.len(),
-                    e.to_string(),
-                )
-            })?;
+            let compressed_data =
+                encode_payload(data, compression_level, None).map_err(|reason| {
+                    CompressionError::compression_failed(
+                        self.backend_name(),
+                        Some(compression_level),
+                        data.len(),
+                        reason,
+                    )
+                })?;
 
             let header = create_header(self.backend_id());
 
@@ -472,26 +3508,95 @@ pub mod zstd_backend {
             Ok(result)
         }
 
+        fn compress_with_deadline(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            deadline: Instant,
+        ) -> CompressionResult<Vec<u8>> {
+            let mut output = Vec::new();
+            self.encode_stream(data, level, Some(deadline), &mut output)?;
+            Ok(output)
+        }
+
+        fn compress_stream(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            output: &mut Vec<u8>,
+        ) -> CompressionResult<()> {
+            self.encode_stream(data, level, None, output)
+        }
+
         fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
-            if !self.is_compressed(data) {
-                return Err(CompressionError::decompression_failed(
-                    self.backend_name(),
-                    data.len(),
-                    "data is not compressed or has invalid header",
-                ));
-            }
+            let mut decompressed_data = Vec::new();
+            self.for_each_frame(data, |header, payload| {
+                let frame_data = decode_payload(payload, self.frame_dictionary(header)?)?;
+                header.verify(&frame_data)?;
+                if decompressed_data.is_empty() {
+                    decompressed_data = frame_data;
+                } else {
+                    decompressed_data.extend_from_slice(&frame_data);
+                }
+                Ok(())
+            })?;
+            Ok(decompressed_data)
+        }
 
-            let compressed_data = &data[HEADER_SIZE..];
+        /// Decodes each frame through a fixed-size buffer to check the integrity fields of the
+        /// header, without allocating the decompressed value
+        fn validate(&self, data: &[u8]) -> CompressionResult<()> {
+            self.for_each_frame(data, |header, payload| {
+                let (length, crc32) = match self.frame_dictionary(header)? {
+                    Some(dictionary) => {
+                        digest_payload(dictionary_payload_reader(payload, dictionary)?)?
+                    }
+                    None => digest_payload(payload_reader(payload)?)?,
+                };
+                header.verify_digest(length, crc32)
+            })
+        }
 
-            let decompressed_data = zstd::decode_all(compressed_data).map_err(|e| {
-                CompressionError::decompression_failed(
+        fn decompress_raw(&self, payload: &[u8]) -> CompressionResult<Vec<u8>> {
+            decode_payload(payload, None).map_err(|reason| {
+                CompressionError::decompression_failed(self.backend_name(), payload.len(), reason)
+            })
+        }
+
+        fn dictionary_epoch(&self, dict_id: DictId) -> Option<DictEpoch> {
+            self.dictionaries
+                .keys()
+                .filter(|(id, _)| *id == dict_id)
+                .map(|(_, epoch)| *epoch)
+                .max()
+        }
+
+        fn compress_with_dictionary(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            dict_id: DictId,
+            epoch: DictEpoch,
+        ) -> CompressionResult<Vec<u8>> {
+            let compression_level = level.unwrap_or(self.default_level);
+            self.validate_compression_level(Some(compression_level))?;
+
+            let failed = |reason: String| {
+                CompressionError::compression_failed(
                     self.backend_name(),
+                    Some(compression_level),
                     data.len(),
-                    e.to_string(),
+                    reason,
                 )
-            })?;
+            };
+            let dictionary = self.dictionary(dict_id, epoch).map_err(failed)?;
+            let compressed_data =
+                encode_payload(data, compression_level, Some(dictionary)).map_err(failed)?;
 
-            Ok(decompressed_data)
+            let mut result = Vec::with_capacity(HEADER_SIZE + compressed_data.len());
+            result.extend_from_slice(&create_header(self.backend_id()));
+            result.extend_from_slice(&compressed_data);
+            Ok(result)
         }
 
         fn is_compressed(&self, data: &[u8]) -> bool {
@@ -502,6 +3607,10 @@ pub mod zstd_backend {
             "zstd"
         }
 
+        fn max_compressed_bound(&self, input_len: usize) -> usize {
+            HEADER_SIZE + compress_bound(input_len)
+        }
+
         fn default_level(&self) -> Option<i32> {
             Some(self.default_level)
         }
@@ -510,26 +3619,31 @@ pub mod zstd_backend {
             CompressionBackendType::Zstd.backend_id()
         }
 
+        fn supports_level(&self, level: i32) -> bool {
+            level_range().contains(&level)
+        }
+
         fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()> {
-            if let Some(level) = level {
-                let range = zstd::compression_level_range();
-                if !range.contains(&level) {
-                    return Err(CompressionError::invalid_configuration(
-                        self.backend_name(),
-                        format!(
-                            "compression level {} is out of valid range {}..={}",
-                            level,
-                            range.start(),
-                            range.end()
-                        ),
-                    ));
-                }
+            if let Some(level) = level
+                && !self.supports_level(level)
+            {
+                let range = level_range();
+                return Err(CompressionError::invalid_configuration(
+                    self.backend_name(),
+                    format!(
+                        "compression level {} is out of valid range {}..={}",
+                        level,
+                        range.start(),
+                        range.end()
+                    ),
+                ));
             }
             Ok(())
         }
     }
 }
 
+#[cfg(feature = "lz4")]
 pub mod lz4_backend {
     use super::*;
 
@@ -566,15 +3680,16 @@ pub mod lz4_backend {
 
             self.validate_compression_level(Some(compression_level))?;
 
-            let original_size =
-                i32::try_from(data.len()).map_err(|_| CompressionError::InvalidConfiguration {
-                    backend: self.backend_name().to_string(),
-                    reason: format!(
+            let original_size = i32::try_from(data.len()).map_err(|_| {
+                CompressionError::invalid_configuration(
+                    self.backend_name(),
+                    format!(
                         "Data too large for LZ4: {} bytes (max: {} bytes)",
                         data.len(),
                         i32::MAX
                     ),
-                })?;
+                )
+            })?;
             let size_bytes = (original_size as u32).to_le_bytes();
 
             // Choose compression mode based on level:
@@ -623,7 +3738,33 @@ pub mod lz4_backend {
                 ));
             }
 
-            let compressed_data = &data[HEADER_SIZE..];
+            check_header_version(data).map_err(|reason| {
+                CompressionError::decompression_failed(self.backend_name(), data.len(), reason)
+            })?;
+
+            let (header, compressed_data, rest) = next_frame(data).ok_or_else(|| {
+                CompressionError::decompression_failed(
+                    self.backend_name(),
+                    data.len(),
+                    "truncated compression header",
+                )
+            })?;
+
+            if !rest.is_empty() {
+                return Err(CompressionError::decompression_failed(
+                    self.backend_name(),
+                    data.len(),
+                    "multiple frames are not supported by lz4",
+                ));
+            }
+
+            if header.dictionary_id.is_some() {
+                return Err(CompressionError::decompression_failed(
+                    self.backend_name(),
+                    data.len(),
+                    "dictionaries are not supported by lz4",
+                ));
+            }
 
             if compressed_data.len() < 4 {
                 return Err(CompressionError::decompression_failed(
@@ -661,6 +3802,10 @@ pub mod lz4_backend {
                 )
             })?;
 
+            header.verify(&decompressed_data).map_err(|reason| {
+                CompressionError::decompression_failed(self.backend_name(), data.len(), reason)
+            })?;
+
             Ok(decompressed_data)
         }
 
@@ -672,6 +3817,13 @@ pub mod lz4_backend {
             "lz4"
         }
 
+        fn max_compressed_bound(&self, input_len: usize) -> usize {
+            // Header, the 4 byte original size prefix and the LZ4 block
+            let block_bound =
+                lz4::block::compress_bound(input_len).unwrap_or(input_len + input_len / 255 + 16);
+            HEADER_SIZE + 4 + block_bound
+        }
+
         fn default_level(&self) -> Option<i32> {
             Some(self.default_level)
         }
@@ -680,44 +3832,416 @@ pub mod lz4_backend {
             CompressionBackendType::Lz4.backend_id()
         }
 
+        fn supports_level(&self, level: i32) -> bool {
+            (LZ4_MIN_LEVEL..=LZ4_MAX_LEVEL).contains(&level)
+        }
+
         fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()> {
             if let Some(level) = level
-                && !(LZ4_MIN_LEVEL..=LZ4_MAX_LEVEL).contains(&level)
+                && !self.supports_level(level)
             {
                 return Err(CompressionError::invalid_configuration(
                     self.backend_name(),
                     format!(
-                        "compression level {} is out of valid range {}..={}",
-                        level, LZ4_MIN_LEVEL, LZ4_MAX_LEVEL
+                        "compression level {} is out of valid range {}..={}",
+                        level, LZ4_MIN_LEVEL, LZ4_MAX_LEVEL
+                    ),
+                ));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Backend wrapper recording its calls, for tests of the layers above the backends
+#[cfg(feature = "test-util")]
+pub mod recording_backend {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A call made to a [`RecordingBackend`], with its inputs and outputs
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum BackendCall {
+        Compress {
+            input: Vec<u8>,
+            level: Option<i32>,
+            output: CompressionResult<Vec<u8>>,
+        },
+        Decompress {
+            input: Vec<u8>,
+            output: CompressionResult<Vec<u8>>,
+        },
+        IsCompressed {
+            input: Vec<u8>,
+            result: bool,
+        },
+    }
+
+    /// Forwards every call to the wrapped backend and records the compress, decompress and
+    /// is_compressed calls. Clones share the same log, so a clone can be handed to a
+    /// [`CompressionManager`] while the test keeps the original to inspect the calls.
+    #[derive(Debug, Clone)]
+    pub struct RecordingBackend {
+        inner: Arc<dyn CompressionBackend>,
+        calls: Arc<Mutex<Vec<BackendCall>>>,
+    }
+
+    impl RecordingBackend {
+        pub fn new(inner: Arc<dyn CompressionBackend>) -> Self {
+            Self {
+                inner,
+                calls: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        /// Returns the calls recorded so far, oldest first
+        pub fn calls(&self) -> Vec<BackendCall> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// Forgets the calls recorded so far
+        pub fn clear(&self) {
+            self.calls.lock().unwrap().clear();
+        }
+
+        fn record(&self, call: BackendCall) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    impl CompressionBackend for RecordingBackend {
+        fn compress(&self, data: &[u8], level: Option<i32>) -> CompressionResult<Vec<u8>> {
+            let output = self.inner.compress(data, level);
+            self.record(BackendCall::Compress {
+                input: data.to_vec(),
+                level,
+                output: output.clone(),
+            });
+            output
+        }
+
+        fn compress_with_deadline(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            deadline: Instant,
+        ) -> CompressionResult<Vec<u8>> {
+            let output = self.inner.compress_with_deadline(data, level, deadline);
+            self.record(BackendCall::Compress {
+                input: data.to_vec(),
+                level,
+                output: output.clone(),
+            });
+            output
+        }
+
+        fn compress_stream(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            output: &mut Vec<u8>,
+        ) -> CompressionResult<()> {
+            let result = self.inner.compress_stream(data, level, output);
+            self.record(BackendCall::Compress {
+                input: data.to_vec(),
+                level,
+                output: result.clone().map(|()| output.clone()),
+            });
+            result
+        }
+
+        fn decompress_raw(&self, payload: &[u8]) -> CompressionResult<Vec<u8>> {
+            let output = self.inner.decompress_raw(payload);
+            self.record(BackendCall::Decompress {
+                input: payload.to_vec(),
+                output: output.clone(),
+            });
+            output
+        }
+
+        fn dictionary_epoch(&self, dict_id: DictId) -> Option<DictEpoch> {
+            self.inner.dictionary_epoch(dict_id)
+        }
+
+        fn compress_with_dictionary(
+            &self,
+            data: &[u8],
+            level: Option<i32>,
+            dict_id: DictId,
+            epoch: DictEpoch,
+        ) -> CompressionResult<Vec<u8>> {
+            let output = self
+                .inner
+                .compress_with_dictionary(data, level, dict_id, epoch);
+            self.record(BackendCall::Compress {
+                input: data.to_vec(),
+                level,
+                output: output.clone(),
+            });
+            output
+        }
+
+        fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+            let output = self.inner.decompress(data);
+            self.record(BackendCall::Decompress {
+                input: data.to_vec(),
+                output: output.clone(),
+            });
+            output
+        }
+
+        fn is_compressed(&self, data: &[u8]) -> bool {
+            let result = self.inner.is_compressed(data);
+            self.record(BackendCall::IsCompressed {
+                input: data.to_vec(),
+                result,
+            });
+            result
+        }
+
+        fn backend_name(&self) -> &'static str {
+            self.inner.backend_name()
+        }
+
+        fn default_level(&self) -> Option<i32> {
+            self.inner.default_level()
+        }
+
+        fn backend_id(&self) -> u8 {
+            self.inner.backend_id()
+        }
+
+        fn supports_level(&self, level: i32) -> bool {
+            self.inner.supports_level(level)
+        }
+
+        fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()> {
+            self.inner.validate_compression_level(level)
+        }
+
+        fn max_compressed_bound(&self, input_len: usize) -> usize {
+            self.inner.max_compressed_bound(input_len)
+        }
+
+        fn validate(&self, data: &[u8]) -> CompressionResult<()> {
+            self.inner.validate(data)
+        }
+    }
+}
+
+/// Compresses the value arguments of a command in place.
+///
+/// Multi-key commands such as MSET must be processed with their full argument list, before a
+/// cluster client splits them per slot: values keep their position next to their key, so each
+/// per-slot fragment carries already compressed values.
+pub fn process_command_args_for_compression(
+    args: &mut [Vec<u8>],
+    request_type: RequestType,
+    compression_manager: Option<&CompressionManager>,
+) -> CompressionResult<()> {
+    let Some(manager) = compression_manager else {
+        return Ok(());
+    };
+
+    if !manager.is_enabled() {
+        return Ok(());
+    }
+
+    let (offset, value_indices) = value_indices(args, request_type, manager);
+    let Some(args) = args.get_mut(offset..) else {
+        return Ok(());
+    };
+    // The key drives routing and cluster slot computation, compressing it would send the
+    // command to the wrong node
+    #[cfg(debug_assertions)]
+    let key = args.first().cloned();
+    for value_index in value_indices {
+        compress_single_value_command(args, manager, value_index, request_type)?;
+    }
+    #[cfg(debug_assertions)]
+    assert_eq!(
+        args.first(),
+        key.as_ref(),
+        "compression modified the key of a {:?} command",
+        request_type
+    );
+    Ok(())
+}
+
+/// Returns the positions of the compressible values, relative to the arguments that follow
+/// the command name, together with the number of leading arguments to skip: custom commands
+/// keep their name as `args[0]`.
+fn value_indices(
+    args: &[Vec<u8>],
+    request_type: RequestType,
+    manager: &CompressionManager,
+) -> (usize, Vec<usize>) {
+    if matches!(request_type, RequestType::CustomCommand) {
+        let value_indices = args
+            .first()
+            .and_then(|name| manager.command_table().get(name))
+            .filter(|entry| entry.behavior == CommandCompressionBehavior::CompressValues)
+            .map(|entry| {
+                entry
+                    .value_indices
+                    .iter()
+                    // Never compress the command name itself
+                    .filter(|&&value_index| value_index != 0)
+                    .map(|&value_index| value_index - 1)
+                    .collect()
+            })
+            .unwrap_or_default();
+        return (1, value_indices);
+    }
+
+    let Some(spec) = command_spec(request_type)
+        .filter(|spec| spec.behavior == CommandCompressionBehavior::CompressValues)
+    else {
+        return (0, Vec::new());
+    };
+
+    let value_indices = match spec.value_positions {
+        ValuePositions::None => Vec::new(),
+        ValuePositions::At(index) => vec![index],
+        ValuePositions::From(start) => (start..args.len()).collect(),
+        ValuePositions::KeyValuePairs => (1..args.len()).step_by(2).collect(),
+        ValuePositions::HashEntries if manager.config().compress_hash_fields => {
+            (1..args.len()).collect()
+        }
+        ValuePositions::HashEntries => (2..args.len()).step_by(2).collect(),
+        ValuePositions::ScriptArgv => {
+            let Some(num_keys) = args
+                .get(1)
+                .and_then(|num_keys| std::str::from_utf8(num_keys).ok())
+                .and_then(|num_keys| num_keys.parse::<usize>().ok())
+            else {
+                return (0, Vec::new());
+            };
+            // numkeys comes from the caller, so the positions are computed without overflowing
+            // and must fall within the arguments
+            let (value_indices, skipped): (Vec<_>, Vec<_>) = manager
+                .config()
+                .compress_eval_argv_indices
+                .iter()
+                .map(|argv_index| {
+                    num_keys
+                        .checked_add(2)
+                        .and_then(|first_argv| first_argv.checked_add(*argv_index))
+                        .filter(|&value_index| value_index < args.len())
+                })
+                .partition(Option::is_some);
+            if !skipped.is_empty() {
+                log_warn(
+                    "compression",
+                    format!(
+                        "Skipping {} script argument position(s) past the end of the arguments, numkeys is {}",
+                        skipped.len(),
+                        num_keys
                     ),
-                ));
+                );
             }
-            Ok(())
+            value_indices.into_iter().flatten().collect()
         }
-    }
+        ValuePositions::StreamEntries => {
+            let id_index = stream_entry_id_index(args);
+            (id_index + 2..args.len()).step_by(2).collect()
+        }
+    };
+    (0, value_indices)
 }
 
-pub fn process_command_args_for_compression(
-    args: &mut [Vec<u8>],
-    request_type: RequestType,
-    compression_manager: Option<&CompressionManager>,
-) -> CompressionResult<()> {
-    let Some(manager) = compression_manager else {
-        return Ok(());
+/// Returns the position of the entry ID in XADD arguments, after the key and the options
+/// `NOMKSTREAM` and `MAXLEN|MINID [=|~] threshold [LIMIT count]`
+fn stream_entry_id_index(args: &[Vec<u8>]) -> usize {
+    let is = |index: usize, option: &[u8]| {
+        args.get(index)
+            .is_some_and(|arg| arg.eq_ignore_ascii_case(option))
     };
 
-    if !manager.is_enabled() {
-        return Ok(());
+    let mut index = 1;
+    loop {
+        if is(index, b"NOMKSTREAM") {
+            index += 1;
+        } else if is(index, b"MAXLEN") || is(index, b"MINID") {
+            index += 1;
+            if is(index, b"=") || is(index, b"~") {
+                index += 1;
+            }
+            // The threshold
+            index += 1;
+            if is(index, b"LIMIT") {
+                index += 2;
+            }
+        } else {
+            return index;
+        }
     }
+}
 
-    let behavior = request_type.compression_behavior();
-    if behavior != CommandCompressionBehavior::CompressValues {
-        return Ok(());
+/// Describes the argument positions [`value_indices`] selects for a built-in command
+fn describe_value_positions(request_type: RequestType, config: &CompressionConfig) -> String {
+    let positions =
+        command_spec(request_type).map_or(ValuePositions::None, |spec| spec.value_positions);
+    match positions {
+        ValuePositions::At(index) => format!("arg[{}]", index),
+        ValuePositions::From(start) => format!("arg[{}..]", start),
+        ValuePositions::KeyValuePairs => "arg[1], arg[3], ...".to_string(),
+        ValuePositions::HashEntries if config.compress_hash_fields => "arg[1..]".to_string(),
+        ValuePositions::HashEntries => "arg[2], arg[4], ...".to_string(),
+        ValuePositions::ScriptArgv if !config.compress_eval_argv_indices.is_empty() => {
+            "the configured ARGV positions".to_string()
+        }
+        ValuePositions::StreamEntries => "the field values after the entry ID".to_string(),
+        ValuePositions::ScriptArgv | ValuePositions::None => "no arguments".to_string(),
     }
+}
 
-    match request_type {
-        RequestType::Set => compress_single_value_command(args, manager, 1),
-        _ => Ok(()),
+/// Commands whose replies are never decompressed, even if registered in the command table.
+/// See [`RequestType::never_decompresses`]; `DEBUG` is only sent as a custom command.
+pub const NEVER_DECOMPRESS_COMMANDS: [&[u8]; 4] = [b"INFO", b"CLIENT", b"DEBUG", b"DUMP"];
+
+/// Returns whether the reply of the named command is always returned verbatim
+pub fn is_never_decompressed_command(command_name: &[u8]) -> bool {
+    NEVER_DECOMPRESS_COMMANDS
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(command_name))
+}
+
+/// Returns whether `SET` arguments (without the command name) include the `GET` option,
+/// in which case the reply carries the previous value.
+pub fn set_returns_old_value<I>(args: I) -> bool
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    // SET key value [options...]
+    args.into_iter()
+        .skip(2)
+        .any(|arg| arg.as_ref().eq_ignore_ascii_case(b"GET"))
+}
+
+/// Returns whether `HRANDFIELD` arguments (without the command name) include the
+/// `WITHVALUES` option, in which case the reply carries field values.
+pub fn hrandfield_returns_values<I>(args: I) -> bool
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    // HRANDFIELD key [count [WITHVALUES]]
+    args.into_iter()
+        .skip(2)
+        .any(|arg| arg.as_ref().eq_ignore_ascii_case(b"WITHVALUES"))
+}
+
+/// Returns the index of the key a value belongs to.
+/// Multi-key commands pair each value with the key right before it.
+fn key_index(request_type: RequestType, value_index: usize) -> usize {
+    if command_spec(request_type)
+        .is_some_and(|spec| spec.value_positions == ValuePositions::KeyValuePairs)
+    {
+        value_index - 1
+    } else {
+        0
     }
 }
 
@@ -725,66 +4249,388 @@ fn compress_single_value_command(
     args: &mut [Vec<u8>],
     manager: &CompressionManager,
     value_index: usize,
+    request_type: RequestType,
 ) -> CompressionResult<()> {
+    // The key must stay intact for routing and cluster slot computation
+    if value_index == 0 {
+        log_warn(
+            "compression",
+            "Refusing to compress the key argument of a command, skipping it",
+        );
+        return Ok(());
+    }
+
     if args.len() <= value_index {
         return Ok(());
     }
 
-    let compressed_value = manager.compress_value(&args[value_index]);
+    let key = &args[key_index(request_type, value_index)];
+    let compressed_value = manager.compress_value_for(key, &args[value_index], request_type);
     args[value_index] = compressed_value.into_owned();
     Ok(())
 }
 
 pub fn process_response_for_decompression(
-    value: redis::Value,
+    mut value: redis::Value,
     request_type: RequestType,
     compression_manager: Option<&CompressionManager>,
 ) -> CompressionResult<redis::Value> {
+    process_response_for_decompression_in_place(&mut value, request_type, compression_manager)?;
+    Ok(value)
+}
+
+/// Decompresses the response in place, without rebuilding arrays or untouched values
+pub fn process_response_for_decompression_in_place(
+    value: &mut redis::Value,
+    request_type: RequestType,
+    compression_manager: Option<&CompressionManager>,
+) -> CompressionResult<()> {
     use redis::Value;
 
     let Some(manager) = compression_manager else {
-        return Ok(value);
+        return Ok(());
     };
 
-    if !manager.is_enabled() {
-        return Ok(value);
+    // Bulk replies too short to be compressed, such as counters, skip the dispatch entirely
+    let eligible = match value {
+        Value::Nil => false,
+        Value::BulkString(data) => manager.should_decompress(data, request_type),
+        Value::SimpleString(data) => manager.should_decompress(data.as_bytes(), request_type),
+        _ => manager.decompresses_replies(request_type),
+    };
+    // SET compresses its arguments, but with the GET option it also replies with the
+    // previous (compressed) value. Callers route SET replies here only when `GET` was given.
+    let Some(spec) = command_spec(request_type).filter(|_| eligible) else {
+        return Ok(());
+    };
+
+    match spec.response_shape {
+        ResponseShape::None => Ok(()),
+        ResponseShape::SingleValue => decompress_single_value_in_place(value, manager),
+        ResponseShape::Array | ResponseShape::Members => decompress_array_in_place(value, manager),
+        ResponseShape::KeyedValue => {
+            decompress_multi_pop_in_place(value, manager, decompress_single_value_in_place)
+        }
+        ResponseShape::MultiPop => {
+            decompress_multi_pop_in_place(value, manager, decompress_array_in_place)
+        }
+        ResponseShape::ScoredMultiPop => {
+            decompress_multi_pop_in_place(value, manager, decompress_scored_members_in_place)
+        }
+        ResponseShape::GeoMembers => decompress_geo_members_in_place(value, manager),
+        ResponseShape::HashEntries => {
+            let fields = manager.config().compress_hash_fields;
+            decompress_hash_entries_in_place(value, manager, fields)
+        }
+        ResponseShape::HashFields if manager.config().compress_hash_fields => {
+            decompress_array_in_place(value, manager)
+        }
+        ResponseShape::HashFields => Ok(()),
+        ResponseShape::StreamEntries => decompress_stream_entries_in_place(value, manager),
+        ResponseShape::StreamsRead => decompress_streams_read_in_place(value, manager),
+    }
+}
+
+/// Decompresses the results of a MULTI/EXEC transaction.
+///
+/// `request_types` lists the queued commands in order; each result is processed according to
+/// the classification of the command that produced it. Results without a matching request type
+/// are returned untouched.
+pub fn process_exec_response(
+    mut results: Vec<redis::Value>,
+    request_types: &[RequestType],
+    compression_manager: Option<&CompressionManager>,
+) -> CompressionResult<Vec<redis::Value>> {
+    for (result, &request_type) in results.iter_mut().zip(request_types) {
+        process_response_for_decompression_in_place(result, request_type, compression_manager)?;
+    }
+    Ok(results)
+}
+
+/// Decompresses the response of a custom command registered in the manager's command table
+pub fn process_custom_command_response(
+    mut value: redis::Value,
+    command_name: &[u8],
+    manager: &CompressionManager,
+) -> CompressionResult<redis::Value> {
+    process_custom_command_response_in_place(&mut value, command_name, manager)?;
+    Ok(value)
+}
+
+/// Decompresses the response of a custom command in place. Replies of commands missing from
+/// the manager's command table are returned without being looked at.
+pub fn process_custom_command_response_in_place(
+    value: &mut redis::Value,
+    command_name: &[u8],
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    if manager.command_table().is_empty()
+        || !manager.is_enabled()
+        || is_never_decompressed_command(command_name)
+        || manager.command_table().behavior(command_name)
+            != CommandCompressionBehavior::DecompressValues
+    {
+        return Ok(());
+    }
+
+    decompress_array_in_place(value, manager)
+}
+
+/// Decompresses every element of an array or set response
+pub fn decompress_array_response(
+    mut value: redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<redis::Value> {
+    decompress_array_in_place(&mut value, manager)?;
+    Ok(value)
+}
+
+/// Positions of a reply tree eligible for decompression by [`decompress_value_tree`].
+/// Map values and the elements of arrays and push messages are always eligible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeDecompressOptions {
+    /// Also decompress the keys of maps
+    pub include_map_keys: bool,
+    /// Decompress the members of sets
+    pub include_set_members: bool,
+}
+
+impl Default for TreeDecompressOptions {
+    fn default() -> Self {
+        Self {
+            include_map_keys: false,
+            include_set_members: true,
+        }
+    }
+}
+
+/// Recursively decompresses every eligible string in a reply, descending into arrays, maps,
+/// sets, push messages and attributes
+pub fn decompress_value_tree(
+    mut value: redis::Value,
+    manager: &CompressionManager,
+    options: TreeDecompressOptions,
+) -> CompressionResult<redis::Value> {
+    decompress_value_tree_in_place(&mut value, manager, options)?;
+    Ok(value)
+}
+
+fn decompress_value_tree_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+    options: TreeDecompressOptions,
+) -> CompressionResult<()> {
+    use redis::Value;
+
+    match value {
+        Value::Array(elements) | Value::Push { data: elements, .. } => elements
+            .iter_mut()
+            .try_for_each(|element| decompress_value_tree_in_place(element, manager, options)),
+        Value::Set(members) if options.include_set_members => members
+            .iter_mut()
+            .try_for_each(|member| decompress_value_tree_in_place(member, manager, options)),
+        Value::Set(_) => Ok(()),
+        Value::Map(entries) => entries.iter_mut().try_for_each(|(key, value)| {
+            if options.include_map_keys {
+                decompress_value_tree_in_place(key, manager, options)?;
+            }
+            decompress_value_tree_in_place(value, manager, options)
+        }),
+        Value::Attribute { data, .. } => decompress_value_tree_in_place(data, manager, options),
+        other => decompress_single_value_in_place(other, manager),
+    }
+}
+
+/// Decompresses every element of an array or set reply, or the reply itself when it is a
+/// single value
+fn decompress_array_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    use redis::Value;
+
+    match value {
+        Value::Array(elements) | Value::Set(elements) => elements
+            .iter_mut()
+            .try_for_each(|element| decompress_single_value_in_place(element, manager)),
+        other => decompress_single_value_in_place(other, manager),
+    }
+}
+
+/// Decompresses the values of HGETALL responses, and the field names when `fields` is set.
+/// Handles both the RESP3 map and the flat RESP2 `[field, value, ...]` array, as well as the
+/// `[[field, value], ...]` pairs of RESP3 HRANDFIELD ... WITHVALUES replies.
+fn decompress_hash_entries_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+    fields: bool,
+) -> CompressionResult<()> {
+    use redis::Value;
+
+    match value {
+        Value::Map(entries) => entries.iter_mut().try_for_each(|(field, value)| {
+            if fields {
+                decompress_single_value_in_place(field, manager)?;
+            }
+            decompress_single_value_in_place(value, manager)
+        }),
+        Value::Array(pairs) if matches!(pairs.first(), Some(Value::Array(_))) => pairs
+            .iter_mut()
+            .try_for_each(|pair| decompress_hash_entries_in_place(pair, manager, fields)),
+        Value::Array(elements) => elements
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| fields || index % 2 == 1)
+            .try_for_each(|(_, element)| decompress_single_value_in_place(element, manager)),
+        _ => Ok(()),
+    }
+}
+
+/// Decompresses the members of `[[member, score], ...]` responses, leaving the scores untouched
+fn decompress_scored_members_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    use redis::Value;
+
+    match value {
+        Value::Array(pairs) => pairs.iter_mut().try_for_each(|pair| match pair {
+            Value::Array(member_and_score) => match member_and_score.first_mut() {
+                Some(member) => decompress_single_value_in_place(member, manager),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }),
+        Value::Map(entries) => entries
+            .iter_mut()
+            .try_for_each(|(member, _score)| decompress_single_value_in_place(member, manager)),
+        _ => Ok(()),
+    }
+}
+
+/// Decompresses the member names of GEOSEARCH responses. With the `WITH*` options each entry
+/// is `[member, distance?, hash?, [longitude, latitude]?]` and only the member is touched.
+fn decompress_geo_members_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    use redis::Value;
+
+    match value {
+        Value::Array(entries) => entries.iter_mut().try_for_each(|entry| match entry {
+            Value::Array(member_and_fields) => match member_and_fields.first_mut() {
+                Some(member) => decompress_single_value_in_place(member, manager),
+                None => Ok(()),
+            },
+            member => decompress_single_value_in_place(member, manager),
+        }),
+        _ => Ok(()),
     }
+}
+
+/// Decompresses the field values of `[[id, [field, value, ...]], ...]` responses, leaving the
+/// entry IDs and field names untouched
+fn decompress_stream_entries_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    use redis::Value;
 
-    let behavior = request_type.compression_behavior();
-    if behavior != CommandCompressionBehavior::DecompressValues {
-        return Ok(value);
+    match value {
+        Value::Array(entries) => entries.iter_mut().try_for_each(|entry| match entry {
+            Value::Array(id_and_fields) if id_and_fields.len() == 2 => {
+                decompress_hash_entries_in_place(&mut id_and_fields[1], manager, false)
+            }
+            _ => Ok(()),
+        }),
+        _ => Ok(()),
     }
+}
+
+/// Decompresses the entries of every stream in XREAD and XREADGROUP responses, leaving the
+/// stream keys untouched
+fn decompress_streams_read_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
+    use redis::Value;
 
-    if matches!(value, Value::Nil) {
-        return Ok(value);
+    match value {
+        Value::Array(streams) => streams.iter_mut().try_for_each(|stream| {
+            decompress_multi_pop_in_place(stream, manager, decompress_stream_entries_in_place)
+        }),
+        Value::Map(_) => {
+            decompress_multi_pop_in_place(value, manager, decompress_stream_entries_in_place)
+        }
+        _ => Ok(()),
     }
+}
+
+/// Decompresses `[key, elements]` responses of the multi-key pop commands.
+/// The popped key name is returned untouched.
+fn decompress_multi_pop_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+    decompress_elements: fn(&mut redis::Value, &CompressionManager) -> CompressionResult<()>,
+) -> CompressionResult<()> {
+    use redis::Value;
 
-    match request_type {
-        RequestType::Get => decompress_single_value_response(value, manager),
-        _ => Ok(value),
+    match value {
+        Value::Array(key_and_elements) if key_and_elements.len() == 2 => {
+            decompress_elements(&mut key_and_elements[1], manager)
+        }
+        Value::Map(entries) => entries
+            .iter_mut()
+            .try_for_each(|(_key, elements)| decompress_elements(elements, manager)),
+        _ => Ok(()),
     }
 }
 
+/// Decompresses a single value response.
+///
+/// A `SimpleString` stays a `SimpleString` when its decompressed content is valid UTF-8.
+/// Otherwise it is returned as a `BulkString`, since a `SimpleString` can't hold binary
+/// data and the original bytes must not be lost. Each value is decompressed only once.
 pub fn decompress_single_value_response(
-    value: redis::Value,
+    mut value: redis::Value,
     manager: &CompressionManager,
 ) -> CompressionResult<redis::Value> {
+    decompress_single_value_in_place(&mut value, manager)?;
+    Ok(value)
+}
+
+/// Decompresses a single value in place. Values that aren't compressed are left untouched.
+fn decompress_single_value_in_place(
+    value: &mut redis::Value,
+    manager: &CompressionManager,
+) -> CompressionResult<()> {
     use redis::Value;
 
     match value {
         Value::BulkString(bytes) => {
-            let decompressed = manager.try_decompress_value(&bytes);
-            Ok(Value::BulkString(decompressed))
+            if let Cow::Owned(decompressed) = manager.try_decompress_value_cow(bytes) {
+                *bytes = decompressed;
+            }
         }
         Value::SimpleString(s) => {
-            let decompressed = manager.try_decompress_value(s.as_bytes());
-            match String::from_utf8(decompressed) {
-                Ok(decompressed_string) => Ok(Value::SimpleString(decompressed_string)),
-                Err(e) => Ok(Value::BulkString(e.into_bytes())),
+            if let Cow::Owned(decompressed) = manager.try_decompress_value_cow(s.as_bytes()) {
+                // Binary content can't be represented as a SimpleString, so the RESP type
+                // changes to BulkString rather than corrupting the bytes
+                *value = match String::from_utf8(decompressed) {
+                    Ok(decompressed_string) => Value::SimpleString(decompressed_string),
+                    Err(e) => Value::BulkString(e.into_bytes()),
+                };
             }
         }
-        _ => Ok(value),
+        // Numeric and boolean scalars are never compressed data, e.g. scores in
+        // WITHSCORES replies, and are passed through untouched.
+        Value::Int(_) | Value::Double(_) | Value::Boolean(_) | Value::BigNumber(_) => {}
+        // Control values, such as the per-command errors of a transaction reply, are never
+        // treated as compressed data either, wherever they appear in the reply.
+        Value::ServerError(_) | Value::Okay | Value::Nil => {}
+        _ => {}
     }
+    Ok(())
 }
 
 /// Magic prefix for compressed data headers (first 3 bytes)
@@ -801,11 +4647,86 @@ pub const CURRENT_VERSION: u8 = 0x00;
 pub const HEADER_SIZE: usize = 5;
 pub const MIN_COMPRESSED_SIZE: usize = HEADER_SIZE + 1;
 
+/// Header version that carries a flags byte after the backend_id.
+/// The flags declare which optional fields follow, in the order of the flag bits.
+/// Multi-byte fields are always little-endian, whatever the byte order of the platform,
+/// so values can be shared between clients on different architectures.
+pub const FLAGS_VERSION: u8 = 0x01;
+pub const HEADER_FLAGS_INDEX: usize = 5;
+
+/// Highest header version this client knows how to read.
+/// Data written by a newer client with a higher version is rejected instead of decoded.
+pub const MAX_SUPPORTED_VERSION: u8 = FLAGS_VERSION;
+
+/// Header flag: the original length is stored as a little-endian u32
+pub const FLAG_ORIGINAL_LENGTH: u8 = 0x01;
+/// Header flag: a CRC32 of the original data is stored as a little-endian u32
+pub const FLAG_CRC32: u8 = 0x02;
+/// Header flag: the compression level is stored as a signed byte
+pub const FLAG_LEVEL: u8 = 0x04;
+/// Header flag: the length of the compressed payload is stored as a little-endian u32,
+/// which allows several frames to be concatenated into one value
+pub const FLAG_FRAME_LENGTH: u8 = 0x08;
+/// Header flag: the pre-shared dictionary the payload was compressed with is stored as its
+/// id, a little-endian u32, followed by its epoch byte
+pub const FLAG_DICTIONARY: u8 = 0x10;
+/// Every header flag this client knows how to read. Headers setting any other bit were
+/// written by a newer client and are rejected instead of misread.
+pub const KNOWN_FLAGS: u8 =
+    FLAG_ORIGINAL_LENGTH | FLAG_CRC32 | FLAG_LEVEL | FLAG_FRAME_LENGTH | FLAG_DICTIONARY;
+
+/// Identifier of a pre-shared compression dictionary, see
+/// [`CompressionConfig::command_dictionaries`]
+pub type DictId = u32;
+
+/// Generation of a pre-shared dictionary. Retraining a dictionary registers it under the same
+/// id with a higher epoch: new values use the latest epoch, and older epochs stay registered
+/// so values written with them remain readable.
+pub type DictEpoch = u8;
+
+/// Upper bound of the header size with every optional field present.
+/// New header fields must keep the header within this size.
+pub const MAX_HEADER_SIZE: usize = 24;
+
+const _: () = assert!(HEADER_SIZE <= MAX_HEADER_SIZE);
+const _: () = assert!(header_size_for(KNOWN_FLAGS) <= MAX_HEADER_SIZE);
+
+/// Returns the exact length of a flags version header declaring `flags`,
+/// i.e. the offset of the compressed payload
+pub const fn header_size_for(flags: u8) -> usize {
+    let mut size = HEADER_SIZE + 1;
+    if flags & FLAG_ORIGINAL_LENGTH != 0 {
+        size += 4;
+    }
+    if flags & FLAG_CRC32 != 0 {
+        size += 4;
+    }
+    if flags & FLAG_LEVEL != 0 {
+        size += 1;
+    }
+    if flags & FLAG_FRAME_LENGTH != 0 {
+        size += 4;
+    }
+    if flags & FLAG_DICTIONARY != 0 {
+        size += 5;
+    }
+    size
+}
+
 /// Checks if data has a valid magic header (any version)
 pub fn has_magic_header(data: &[u8]) -> bool {
     data.len() >= HEADER_SIZE && data[0..3] == MAGIC_PREFIX
 }
 
+/// Cheap check whether `data` may be a value compressed by this client: it starts with the
+/// magic prefix and is long enough to hold a header and a payload.
+///
+/// Used as a pre-filter before dispatching to a specific backend, which performs the
+/// full header validation.
+pub fn is_glide_compressed(data: &[u8]) -> bool {
+    data.len() >= MIN_COMPRESSED_SIZE && data[0..3] == MAGIC_PREFIX
+}
+
 /// Extracts the version byte from the header
 /// Returns None if the data doesn't have a valid magic header
 pub fn extract_version(data: &[u8]) -> Option<u8> {
@@ -826,11 +4747,34 @@ pub fn extract_backend_id(data: &[u8]) -> Option<u8> {
     }
 }
 
+/// Extracts the compression level recorded in the header.
+/// Returns None if the data has no header or the level was not recorded.
+pub fn extract_level(data: &[u8]) -> Option<i32> {
+    FrameHeader::parse(data)?.level.map(i32::from)
+}
+
 /// Checks if the data has a valid magic header with the current version
 pub fn has_current_version_header(data: &[u8]) -> bool {
     extract_version(data) == Some(CURRENT_VERSION)
 }
 
+/// Rejects data whose header version is newer than [`MAX_SUPPORTED_VERSION`], or whose
+/// flags set bits outside [`KNOWN_FLAGS`]
+pub fn check_header_version(data: &[u8]) -> Result<(), String> {
+    match extract_version(data) {
+        Some(version) if version > MAX_SUPPORTED_VERSION => {
+            Err(format!("unsupported header version {}", version))
+        }
+        Some(FLAGS_VERSION) => match data.get(HEADER_FLAGS_INDEX) {
+            Some(flags) if flags & !KNOWN_FLAGS != 0 => {
+                Err(format!("unsupported header flags {:#04x}", flags))
+            }
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
 /// Creates a compression header with the current version
 pub fn create_header(backend_id: u8) -> [u8; HEADER_SIZE] {
     create_header_with_version(backend_id, CURRENT_VERSION)
@@ -846,6 +4790,251 @@ pub fn create_header_with_version(backend_id: u8, version: u8) -> [u8; HEADER_SI
     header
 }
 
+/// Parsed compression header, including the optional fields of the flags version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub version: u8,
+    pub backend_id: u8,
+    pub flags: u8,
+    pub original_length: Option<u32>,
+    pub crc32: Option<u32>,
+    pub level: Option<i8>,
+    pub frame_length: Option<u32>,
+    pub dictionary_id: Option<DictId>,
+    /// Epoch of the dictionary, 0 without one
+    pub dictionary_epoch: DictEpoch,
+}
+
+impl FrameHeader {
+    /// Creates a legacy header without optional fields
+    pub fn new(backend_id: u8) -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            backend_id,
+            flags: 0,
+            original_length: None,
+            crc32: None,
+            level: None,
+            frame_length: None,
+            dictionary_id: None,
+            dictionary_epoch: 0,
+        }
+    }
+
+    /// Creates a header carrying the integrity fields of `mode` for `original`
+    pub fn with_integrity(backend_id: u8, mode: IntegrityMode, original: &[u8]) -> Self {
+        let flags = mode.header_flags();
+        if flags == 0 {
+            return Self::new(backend_id);
+        }
+
+        Self {
+            version: FLAGS_VERSION,
+            backend_id,
+            flags,
+            original_length: (flags & FLAG_ORIGINAL_LENGTH != 0).then_some(original.len() as u32),
+            crc32: (flags & FLAG_CRC32 != 0).then(|| crc32fast::hash(original)),
+            level: None,
+            frame_length: None,
+            dictionary_id: None,
+            dictionary_epoch: 0,
+        }
+    }
+
+    /// Records the compression level. Levels that don't fit a signed byte are not recorded.
+    pub fn with_level(self, level: i32) -> Self {
+        let Ok(level) = i8::try_from(level) else {
+            return self;
+        };
+        Self {
+            version: FLAGS_VERSION,
+            flags: self.flags | FLAG_LEVEL,
+            level: Some(level),
+            ..self
+        }
+    }
+
+    /// Records the length of the compressed payload following the header.
+    /// Payloads that don't fit a u32 are not recorded.
+    pub fn with_frame_length(self, length: usize) -> Self {
+        let Ok(length) = u32::try_from(length) else {
+            return self;
+        };
+        Self {
+            version: FLAGS_VERSION,
+            flags: self.flags | FLAG_FRAME_LENGTH,
+            frame_length: Some(length),
+            ..self
+        }
+    }
+
+    /// Records the id and epoch of the dictionary the payload was compressed with
+    pub fn with_dictionary(self, dictionary_id: DictId, epoch: DictEpoch) -> Self {
+        Self {
+            version: FLAGS_VERSION,
+            flags: self.flags | FLAG_DICTIONARY,
+            dictionary_id: Some(dictionary_id),
+            dictionary_epoch: epoch,
+            ..self
+        }
+    }
+
+    /// Returns the encoded length of this header
+    pub fn encoded_len(&self) -> usize {
+        if self.version != FLAGS_VERSION {
+            return HEADER_SIZE;
+        }
+        header_size_for(self.flags)
+    }
+
+    /// Parses the header at the start of `data`.
+    /// Returns None if there is no magic header, the flags set bits outside [`KNOWN_FLAGS`]
+    /// or the optional fields are truncated.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if !has_magic_header(data) {
+            return None;
+        }
+
+        let version = data[HEADER_VERSION_INDEX];
+        let backend_id = data[HEADER_BACKEND_INDEX];
+        if version != FLAGS_VERSION {
+            return Some(Self {
+                version,
+                ..Self::new(backend_id)
+            });
+        }
+
+        let flags = *data.get(HEADER_FLAGS_INDEX)?;
+        if flags & !KNOWN_FLAGS != 0 {
+            return None;
+        }
+        let mut offset = HEADER_FLAGS_INDEX + 1;
+        let mut take = |len: usize| {
+            let bytes = data.get(offset..offset + len)?;
+            offset += len;
+            Some(bytes)
+        };
+        let le_u32 = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        let original_length = if flags & FLAG_ORIGINAL_LENGTH != 0 {
+            Some(le_u32(take(4)?))
+        } else {
+            None
+        };
+        let crc32 = if flags & FLAG_CRC32 != 0 {
+            Some(le_u32(take(4)?))
+        } else {
+            None
+        };
+        let level = if flags & FLAG_LEVEL != 0 {
+            Some(take(1)?[0] as i8)
+        } else {
+            None
+        };
+        let frame_length = if flags & FLAG_FRAME_LENGTH != 0 {
+            Some(le_u32(take(4)?))
+        } else {
+            None
+        };
+        let (dictionary_id, dictionary_epoch) = if flags & FLAG_DICTIONARY != 0 {
+            (Some(le_u32(take(4)?)), take(1)?[0])
+        } else {
+            (None, 0)
+        };
+
+        Some(Self {
+            version,
+            backend_id,
+            flags,
+            original_length,
+            crc32,
+            level,
+            frame_length,
+            dictionary_id,
+            dictionary_epoch,
+        })
+    }
+
+    /// Encodes the header. Multi-byte fields are little-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        bytes.extend_from_slice(&create_header_with_version(self.backend_id, self.version));
+        if self.version == FLAGS_VERSION {
+            bytes.push(self.flags);
+            if let Some(length) = self.original_length {
+                bytes.extend_from_slice(&length.to_le_bytes());
+            }
+            if let Some(crc) = self.crc32 {
+                bytes.extend_from_slice(&crc.to_le_bytes());
+            }
+            if let Some(level) = self.level {
+                bytes.push(level as u8);
+            }
+            if let Some(length) = self.frame_length {
+                bytes.extend_from_slice(&length.to_le_bytes());
+            }
+            if let Some(dictionary_id) = self.dictionary_id {
+                bytes.extend_from_slice(&dictionary_id.to_le_bytes());
+                bytes.push(self.dictionary_epoch);
+            }
+        }
+        bytes
+    }
+
+    /// Verifies the decompressed data against the integrity fields declared by the header
+    pub fn verify(&self, decompressed: &[u8]) -> Result<(), String> {
+        self.check(decompressed.len(), || crc32fast::hash(decompressed))
+    }
+
+    /// Like [`Self::verify`], for decompressed data that was only streamed through: `length`
+    /// and `crc32` describe the decompressed bytes
+    pub fn verify_digest(&self, length: usize, crc32: u32) -> Result<(), String> {
+        self.check(length, || crc32)
+    }
+
+    fn check(&self, length: usize, crc32: impl FnOnce() -> u32) -> Result<(), String> {
+        if let Some(expected) = self.original_length
+            && expected as usize != length
+        {
+            return Err(format!(
+                "length mismatch: header declares {} bytes, got {} bytes",
+                expected, length
+            ));
+        }
+        if let Some(expected) = self.crc32 {
+            let actual = crc32();
+            if expected != actual {
+                return Err(format!(
+                    "checksum mismatch: expected 0x{:08x}, got 0x{:08x}",
+                    expected, actual
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits compressed data into its parsed header and the compressed payload.
+/// For a header with a frame length, the payload stops at the end of the frame.
+pub fn split_frame(data: &[u8]) -> Option<(FrameHeader, &[u8])> {
+    next_frame(data).map(|(header, payload, _)| (header, payload))
+}
+
+/// Splits the first frame off `data`, returning its header, its payload and the bytes following it.
+/// Only frames with a recorded frame length can be followed by further bytes.
+/// Returns None if the header is invalid or the payload is shorter than the frame length.
+pub fn next_frame(data: &[u8]) -> Option<(FrameHeader, &[u8], &[u8])> {
+    let header = FrameHeader::parse(data)?;
+    let rest = &data[header.encoded_len()..];
+    match header.frame_length {
+        Some(length) => {
+            let length = length as usize;
+            (rest.len() >= length).then(|| (header, &rest[..length], &rest[length..]))
+        }
+        None => Some((header, rest, &[][..])),
+    }
+}
+
 /// Lazy-initialized static backends for decompression-only operations.
 ///
 /// These backends are shared across all compression managers to avoid repeated allocations
@@ -859,17 +5048,22 @@ pub fn create_header_with_version(backend_id: u8, version: u8) -> [u8; HEADER_SI
 ///    compressed data, respecting the client's specific configuration
 ///
 /// Thread-safe initialization is guaranteed by `OnceLock`.
+#[cfg(any(feature = "zstd", feature = "wasm", feature = "lz4"))]
 mod static_backends {
     use super::*;
     use std::sync::OnceLock;
 
+    #[cfg(any(feature = "zstd", feature = "wasm"))]
     static ZSTD_BACKEND: OnceLock<zstd_backend::ZstdBackend> = OnceLock::new();
+    #[cfg(feature = "lz4")]
     static LZ4_BACKEND: OnceLock<lz4_backend::Lz4Backend> = OnceLock::new();
 
+    #[cfg(any(feature = "zstd", feature = "wasm"))]
     pub fn get_zstd_backend() -> &'static zstd_backend::ZstdBackend {
         ZSTD_BACKEND.get_or_init(zstd_backend::ZstdBackend::new)
     }
 
+    #[cfg(feature = "lz4")]
     pub fn get_lz4_backend() -> &'static lz4_backend::Lz4Backend {
         LZ4_BACKEND.get_or_init(lz4_backend::Lz4Backend::new)
     }
@@ -880,10 +5074,17 @@ mod static_backends {
 fn get_backend_for_decompression(
     backend_id: u8,
 ) -> CompressionResult<&'static dyn CompressionBackend> {
-    match backend_id {
-        0x01 => Ok(static_backends::get_zstd_backend()),
-        0x02 => Ok(static_backends::get_lz4_backend()),
-        _ => Err(CompressionError::unsupported_backend(format!(
+    match CompressionBackendType::by_id(backend_id) {
+        #[cfg(any(feature = "zstd", feature = "wasm"))]
+        Some(CompressionBackendType::Zstd) => Ok(static_backends::get_zstd_backend()),
+        #[cfg(feature = "lz4")]
+        Some(CompressionBackendType::Lz4) => Ok(static_backends::get_lz4_backend()),
+        #[allow(unreachable_patterns)]
+        Some(backend_type) => Err(CompressionError::unsupported_backend(format!(
+            "{} support not compiled in",
+            backend_type
+        ))),
+        None => Err(CompressionError::unsupported_backend(format!(
             "backend ID 0x{:02x}",
             backend_id
         ))),
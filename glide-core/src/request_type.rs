@@ -6,7 +6,7 @@ use redis::{Cmd, cmd};
 use crate::command_request::RequestType as ProtobufRequestType;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RequestType {
     /// Invalid request type
     InvalidRequest = 0,
@@ -836,14 +836,30 @@ impl From<::protobuf::EnumOrUnknown<ProtobufRequestType>> for RequestType {
 }
 
 impl RequestType {
-    /// Returns the compression behavior for this request type.
+    /// Returns the compression behavior for this request type, as declared in
+    /// [`COMMAND_SPECS`](crate::compression::COMMAND_SPECS).
+    ///
+    /// Everything else is `NoCompression`, in particular metadata and bit-level commands
+    /// (OBJECT ENCODING, BITFIELD, BITCOUNT, ...) that operate on the stored bytes as-is,
+    /// numeric hash field updates that must stay parseable by the server, and DUMP/RESTORE
+    /// whose payloads wrap the stored bytes in the server's serialization format.
     pub fn compression_behavior(self) -> crate::compression::CommandCompressionBehavior {
-        use crate::compression::CommandCompressionBehavior;
-        match self {
-            RequestType::Set => CommandCompressionBehavior::CompressValues,
-            RequestType::Get => CommandCompressionBehavior::DecompressValues,
-            _ => CommandCompressionBehavior::NoCompression,
-        }
+        use crate::compression::{CommandCompressionBehavior, command_spec};
+        command_spec(self).map_or(CommandCompressionBehavior::NoCompression, |spec| {
+            spec.behavior
+        })
+    }
+
+    /// Returns whether the reply of this request is always returned verbatim.
+    ///
+    /// Unlike the `NoCompression` default, this is a hard guarantee for diagnostic commands
+    /// and for DUMP payloads, even if their output happens to start with the compression
+    /// magic bytes.
+    pub fn never_decompresses(self) -> bool {
+        matches!(
+            self,
+            RequestType::Info | RequestType::ClientInfo | RequestType::Dump
+        )
     }
 
     /// Returns a `Cmd` set with the command name matching the request.
@@ -357,10 +357,11 @@ async fn send_command(
 /// Process batch response for decompression
 ///
 /// This function processes the response from a batch operation (pipeline or transaction)
-/// and decompresses individual response values using magic header detection.
+/// and decompresses each result according to the command of the batch that produced it.
 ///
 /// # Arguments
 /// * `response` - The batch response value (typically an array)
+/// * `pipeline` - The pipeline the response was received for
 /// * `client` - The client instance containing the compression manager
 ///
 /// # Returns
@@ -368,6 +369,7 @@ async fn send_command(
 /// * `Err(CompressionError)` - If critical decompression errors occur
 fn process_batch_response_for_decompression(
     response: redis::Value,
+    pipeline: &redis::Pipeline,
     client: &Client,
 ) -> Result<redis::Value, crate::compression::CompressionError> {
     use redis::Value;
@@ -385,27 +387,12 @@ fn process_batch_response_for_decompression(
         return Ok(response);
     }
 
-    // Process based on response type
     match response {
-        Value::Array(responses) => {
-            // Process each response using the existing decompression function
-            let mut processed_responses = Vec::with_capacity(responses.len());
-            for response in responses {
-                let processed_response = match crate::compression::decompress_single_value_response(
-                    response.clone(),
-                    manager,
-                ) {
-                    Ok(decompressed) => decompressed,
-                    Err(_) => response, // Return original on error
-                };
-                processed_responses.push(processed_response);
-            }
-
-            Ok(Value::Array(processed_responses))
+        Value::Array(results) => {
+            crate::client::decompress_batch_results(results, pipeline, manager).map(Value::Array)
         }
-
-        // For non-array responses, try to decompress directly
-        other => crate::compression::decompress_single_value_response(other, manager),
+        // Aborted transactions reply with nil
+        other => Ok(other),
     }
 }
 
@@ -437,18 +424,47 @@ fn process_command_for_compression(
     let command_str = String::from_utf8_lossy(command_name).to_uppercase();
     let request_type = match command_str.as_str() {
         "SET" => crate::request_type::RequestType::Set,
+        "HSET" => crate::request_type::RequestType::HSet,
+        "HSETNX" => crate::request_type::RequestType::HSetNX,
+        "LSET" => crate::request_type::RequestType::LSet,
+        "LINSERT" => crate::request_type::RequestType::LInsert,
+        "LPUSH" => crate::request_type::RequestType::LPush,
+        "RPUSH" => crate::request_type::RequestType::RPush,
+        "MSET" => crate::request_type::RequestType::MSet,
+        "EVAL" => crate::request_type::RequestType::Eval,
+        "EVALSHA" => crate::request_type::RequestType::EvalSha,
+        "FCALL" => crate::request_type::RequestType::FCall,
+        "XADD" => crate::request_type::RequestType::XAdd,
+        // Module commands registered in the compression command table
+        _ if compression_manager_ref
+            .is_some_and(|manager| manager.command_table().contains(command_name)) =>
+        {
+            crate::request_type::RequestType::CustomCommand
+        }
         _ => return Ok(()), // Unknown command, no compression needed
     };
 
+    // Custom commands keep the command name as the first argument
+    let is_custom_command = matches!(
+        request_type,
+        crate::request_type::RequestType::CustomCommand
+    );
+
     // Get arguments excluding the command name
-    let mut args: Vec<Vec<u8>> = all_args[1..].to_vec();
+    let mut args: Vec<Vec<u8>> = if is_custom_command {
+        all_args.clone()
+    } else {
+        all_args[1..].to_vec()
+    };
 
     // Process arguments for compression
     process_command_args_for_compression(&mut args, request_type, compression_manager_ref)?;
 
     // Rebuild the command with potentially compressed arguments
     *cmd = redis::Cmd::new();
-    cmd.arg(command_name); // Add the command name back
+    if !is_custom_command {
+        cmd.arg(command_name); // Add the command name back
+    }
     for arg in args {
         cmd.arg(arg);
     }
@@ -587,7 +603,7 @@ async fn send_batch(
     // Process response for decompression if needed
     let processed_res = match res {
         Ok(value) => {
-            match process_batch_response_for_decompression(value.clone(), client) {
+            match process_batch_response_for_decompression(value.clone(), &pipeline, client) {
                 Ok(processed_value) => Ok(processed_value),
                 Err(e) => {
                     // Log decompression error but return original response
@@ -396,7 +396,7 @@ mod compression_tests {
 
     #[test]
     fn test_version_api() {
-        let future_version = CURRENT_VERSION + 1;
+        let future_version = FLAGS_VERSION + 1;
         let max_version = 0xFF;
         // Alarm if we've reached version 255
         // Version 255 should introduce changes to support an additional versioning byte
@@ -464,12 +464,15 @@ mod compression_tests {
             Some(CompressionBackendType::Zstd.backend_id())
         );
 
-        // The decompression should still work because the underlying format is the same
-        // This demonstrates forward compatibility - newer versions can be read by older clients
-        let decompressed_future = manager
+        // A version newer than this client understands must not be fed to the decoder
+        let err = manager
             .decompress_value(&future_version_compressed)
-            .unwrap();
-        assert_eq!(decompressed_future, test_data);
+            .unwrap_err();
+        assert!(matches!(err, CompressionError::DecompressionFailed { .. }));
+        assert!(
+            err.to_string()
+                .contains(&format!("unsupported header version {}", future_version))
+        );
     }
 
     #[test]
@@ -499,6 +502,8 @@ mod compression_tests {
         assert!(err.to_string().contains("corrupted"));
     }
 
+    // The pure-Rust encoder of the wasm feature doesn't shrink inputs this short
+    #[cfg(not(feature = "wasm"))]
     #[test]
     fn test_zstd_backend_compress_decompress_roundtrip() {
         use glide_core::compression::zstd_backend::ZstdBackend;
@@ -652,6 +657,8 @@ mod compression_tests {
         assert!(manager.is_enabled());
     }
 
+    // The pure-Rust encoder of the wasm feature doesn't shrink inputs this short
+    #[cfg(not(feature = "wasm"))]
     #[test]
     fn test_compression_manager_decompress_scenarios() {
         use glide_core::compression::zstd_backend::ZstdBackend;
@@ -696,6 +703,8 @@ mod compression_tests {
         assert!(err.to_string().contains("ZSTD"));
     }
 
+    // The pure-Rust encoder of the wasm feature doesn't shrink inputs this short
+    #[cfg(not(feature = "wasm"))]
     #[test]
     fn test_cross_backend_decompression() {
         use glide_core::compression::lz4_backend::Lz4Backend;
@@ -760,4 +769,4839 @@ mod compression_tests {
         let result = manager.try_decompress_value(&unsupported_data);
         assert_eq!(result, unsupported_data);
     }
+
+    #[test]
+    fn test_compress_value_with_level_override() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let original_data = "The quick brown fox jumps over the lazy dog. ".repeat(200);
+
+        // Compress the same input at a low and a high level
+        let low = manager
+            .compress_value_with_level(original_data.as_bytes(), Some(1))
+            .unwrap();
+        let high = manager
+            .compress_value_with_level(original_data.as_bytes(), Some(19))
+            .unwrap();
+        assert!(has_magic_header(&low));
+        assert!(has_magic_header(&high));
+        assert!(high.len() <= low.len());
+
+        // Both outputs decompress to the original data
+        assert_eq!(
+            manager.decompress_value(&low).unwrap(),
+            original_data.as_bytes()
+        );
+        assert_eq!(
+            manager.decompress_value(&high).unwrap(),
+            original_data.as_bytes()
+        );
+
+        // Size gating still applies
+        let small_data = b"small";
+        let result = manager
+            .compress_value_with_level(small_data, Some(19))
+            .unwrap();
+        assert_eq!(result, small_data);
+
+        // The override level is validated against the backend
+        let result = manager.compress_value_with_level(original_data.as_bytes(), Some(999));
+        assert!(matches!(
+            result,
+            Err(CompressionError::InvalidConfiguration { .. })
+        ));
+    }
+
+    #[test]
+    fn test_metadata_and_bit_commands_never_decompress() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let original_data = "metadata command payload ".repeat(20);
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert!(has_magic_header(&compressed));
+
+        let request_types = [
+            RequestType::ObjectEncoding,
+            RequestType::ObjectRefCount,
+            RequestType::BitField,
+            RequestType::BitFieldReadOnly,
+            RequestType::BitCount,
+            RequestType::BitPos,
+        ];
+
+        for request_type in request_types {
+            assert_eq!(
+                request_type.compression_behavior(),
+                CommandCompressionBehavior::NoCompression
+            );
+
+            // Responses that happen to carry compressed bytes are returned verbatim
+            let response = process_response_for_decompression(
+                Value::BulkString(compressed.clone()),
+                request_type,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(response, Value::BulkString(compressed.clone()));
+
+            // Arguments are never compressed
+            let mut args = vec![b"key".to_vec(), original_data.as_bytes().to_vec()];
+            process_command_args_for_compression(&mut args, request_type, Some(&manager)).unwrap();
+            assert_eq!(args[1], original_data.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_compression_manager_builder() {
+        // Build a valid manager with every option set
+        let manager = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Lz4)
+            .level(5)
+            .min_size(128)
+            .max_size(4096)
+            .build()
+            .unwrap();
+        assert_eq!(manager.backend_name(), "lz4");
+        assert!(manager.is_enabled());
+        assert_eq!(manager.config().backend, CompressionBackendType::Lz4);
+        assert_eq!(manager.config().compression_level, Some(5));
+        assert_eq!(manager.config().min_compression_size, 128);
+        assert_eq!(manager.config().max_compression_size, Some(4096));
+
+        // Size bounds are applied
+        assert!(!manager.should_compress(&[b'A'; 127]));
+        assert!(manager.should_compress(&[b'A'; 128]));
+        assert!(manager.should_compress(&[b'A'; 4096]));
+        assert!(!manager.should_compress(&[b'A'; 4097]));
+
+        // Defaults come from the selected backend
+        let manager = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Zstd)
+            .build()
+            .unwrap();
+        assert_eq!(manager.backend_name(), "zstd");
+        assert_eq!(manager.config().compression_level, Some(3));
+        assert_eq!(manager.config().min_compression_size, 64);
+        assert_eq!(manager.config().max_compression_size, None);
+
+        // Invalid level is rejected by the backend validation
+        let err = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Zstd)
+            .level(999)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, CompressionError::InvalidConfiguration { .. }));
+        assert!(err.to_string().contains("999"));
+
+        // max_size below min_size is rejected
+        let err = CompressionManagerBuilder::new()
+            .min_size(1024)
+            .max_size(512)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, CompressionError::InvalidConfiguration { .. }));
+        assert!(err.to_string().contains("max_compression_size"));
+    }
+
+    #[test]
+    fn test_hsetnx_compresses_value_and_hincrby_untouched() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let value = "hash field value that compresses well ".repeat(10);
+
+        assert_eq!(
+            RequestType::HSetNX.compression_behavior(),
+            CommandCompressionBehavior::CompressValues
+        );
+        assert_eq!(
+            RequestType::HIncrBy.compression_behavior(),
+            CommandCompressionBehavior::NoCompression
+        );
+        assert_eq!(
+            RequestType::HIncrByFloat.compression_behavior(),
+            CommandCompressionBehavior::NoCompression
+        );
+
+        // HSETNX key field value - only the value is compressed
+        let mut args = vec![
+            b"key".to_vec(),
+            b"field".to_vec(),
+            value.as_bytes().to_vec(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::HSetNX, Some(&manager))
+            .unwrap();
+        assert_eq!(args[0], b"key");
+        assert_eq!(args[1], b"field");
+        assert!(has_magic_header(&args[2]));
+        assert_eq!(
+            manager.decompress_value(&args[2]).unwrap(),
+            value.as_bytes()
+        );
+
+        // HGET key field - the stored value is read back decompressed
+        let response = process_response_for_decompression(
+            redis::Value::BulkString(args[2].clone()),
+            RequestType::HGet,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            redis::Value::BulkString(value.as_bytes().to_vec())
+        );
+
+        // HINCRBY key field increment - nothing is touched
+        let increment = "1".repeat(100);
+        let mut args = vec![
+            b"key".to_vec(),
+            b"field".to_vec(),
+            increment.as_bytes().to_vec(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::HIncrBy, Some(&manager))
+            .unwrap();
+        assert_eq!(args[2], increment.as_bytes());
+    }
+
+    #[test]
+    fn test_integrity_modes_mixed_decoding() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original_data = "integrity protected payload ".repeat(20);
+
+        let plain_manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let length_manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_integrity_mode(IntegrityMode::LengthOnly),
+        )
+        .unwrap();
+        let crc_manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_integrity_mode(IntegrityMode::Crc32),
+        )
+        .unwrap();
+
+        let plain = plain_manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        let with_length = length_manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        let with_crc = crc_manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+
+        // The header declares the integrity fields
+        let header = FrameHeader::parse(&plain).unwrap();
+        assert_eq!(header.version, CURRENT_VERSION);
+        assert_eq!(header.flags, 0);
+        assert_eq!(header.encoded_len(), HEADER_SIZE);
+
+        let header = FrameHeader::parse(&with_length).unwrap();
+        assert_eq!(header.version, FLAGS_VERSION);
+        assert_eq!(header.flags, FLAG_ORIGINAL_LENGTH);
+        assert_eq!(header.original_length, Some(original_data.len() as u32));
+        assert_eq!(header.crc32, None);
+
+        let header = FrameHeader::parse(&with_crc).unwrap();
+        assert_eq!(header.version, FLAGS_VERSION);
+        assert_eq!(header.flags, FLAG_ORIGINAL_LENGTH | FLAG_CRC32);
+        assert_eq!(
+            header.crc32,
+            Some(crc32fast::hash(original_data.as_bytes()))
+        );
+
+        // A single manager decodes all of them, regardless of its own integrity mode
+        for manager in [&plain_manager, &crc_manager] {
+            for compressed in [&plain, &with_length, &with_crc] {
+                assert_eq!(
+                    manager.decompress_value(compressed).unwrap(),
+                    original_data.as_bytes()
+                );
+            }
+        }
+
+        // A corrupted checksum is detected
+        let mut corrupted = with_crc.clone();
+        let crc_offset = HEADER_FLAGS_INDEX + 1 + 4;
+        corrupted[crc_offset] ^= 0xFF;
+        let err = plain_manager.decompress_value(&corrupted).unwrap_err();
+        assert!(matches!(err, CompressionError::DecompressionFailed { .. }));
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        // A wrong original length is detected
+        let mut corrupted = with_length.clone();
+        corrupted[HEADER_FLAGS_INDEX + 1] ^= 0x01;
+        let err = plain_manager.decompress_value(&corrupted).unwrap_err();
+        assert!(err.to_string().contains("length mismatch"));
+
+        // LZ4 frames carry the same integrity fields
+        let lz4_manager = CompressionManager::new(
+            Box::new(glide_core::compression::lz4_backend::Lz4Backend::new()),
+            CompressionConfig::new(CompressionBackendType::Lz4)
+                .with_integrity_mode(IntegrityMode::Crc32),
+        )
+        .unwrap();
+        let lz4_compressed = lz4_manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(
+            FrameHeader::parse(&lz4_compressed).unwrap().flags,
+            FLAG_ORIGINAL_LENGTH | FLAG_CRC32
+        );
+        assert_eq!(
+            plain_manager.decompress_value(&lz4_compressed).unwrap(),
+            original_data.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_backend_supported_levels() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let zstd = ZstdBackend::new();
+        let zstd_range = zstd::compression_level_range();
+        assert!(zstd.supports_level(1));
+        assert!(zstd.supports_level(3));
+        assert!(zstd.supports_level(22));
+        assert!(zstd.supports_level(*zstd_range.start()));
+        assert!(!zstd.supports_level(23));
+        assert!(!zstd.supports_level(*zstd_range.start() - 1));
+        assert!(zstd.validate_compression_level(Some(22)).is_ok());
+        assert!(zstd.validate_compression_level(Some(23)).is_err());
+        assert!(zstd.validate_compression_level(None).is_ok());
+
+        let lz4 = Lz4Backend::new();
+        assert!(lz4.supports_level(-128));
+        assert!(lz4.supports_level(0));
+        assert!(lz4.supports_level(12));
+        assert!(!lz4.supports_level(-129));
+        assert!(!lz4.supports_level(13));
+        assert!(lz4.validate_compression_level(Some(12)).is_ok());
+        assert!(lz4.validate_compression_level(Some(13)).is_err());
+        assert!(lz4.validate_compression_level(None).is_ok());
+
+        // Compression uses the same rules
+        assert!(zstd.compress(b"data", Some(23)).is_err());
+        assert!(lz4.compress(b"data", Some(13)).is_err());
+    }
+
+    #[test]
+    fn test_multi_pop_response_decompression() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let first = "first list element ".repeat(10);
+        let second = "second list element ".repeat(10);
+        let compressed_first = manager.compress_value(first.as_bytes()).into_owned();
+        let compressed_second = manager.compress_value(second.as_bytes()).into_owned();
+        assert!(has_magic_header(&compressed_first));
+
+        assert_eq!(
+            RequestType::LMPop.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        );
+        assert_eq!(
+            RequestType::ZMPop.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        );
+
+        // LMPOP: [key, [elements...]] with a mix of compressed and raw elements
+        let response = Value::Array(vec![
+            Value::BulkString(b"mylist".to_vec()),
+            Value::Array(vec![
+                Value::BulkString(compressed_first.clone()),
+                Value::BulkString(b"raw".to_vec()),
+                Value::BulkString(compressed_second.clone()),
+            ]),
+        ]);
+        let result =
+            process_response_for_decompression(response, RequestType::LMPop, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::BulkString(b"mylist".to_vec()),
+                Value::Array(vec![
+                    Value::BulkString(first.as_bytes().to_vec()),
+                    Value::BulkString(b"raw".to_vec()),
+                    Value::BulkString(second.as_bytes().to_vec()),
+                ]),
+            ])
+        );
+
+        // LMPOP as a RESP3 map
+        let response = Value::Map(vec![(
+            Value::BulkString(b"mylist".to_vec()),
+            Value::Array(vec![Value::BulkString(compressed_first.clone())]),
+        )]);
+        let result =
+            process_response_for_decompression(response, RequestType::LMPop, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            result,
+            Value::Map(vec![(
+                Value::BulkString(b"mylist".to_vec()),
+                Value::Array(vec![Value::BulkString(first.as_bytes().to_vec())]),
+            )])
+        );
+
+        // ZMPOP: [key, [[member, score], ...]] - scores are untouched
+        let response = Value::Array(vec![
+            Value::BulkString(b"myzset".to_vec()),
+            Value::Array(vec![
+                Value::Array(vec![
+                    Value::BulkString(compressed_first.clone()),
+                    Value::Double(1.5),
+                ]),
+                Value::Array(vec![
+                    Value::BulkString(compressed_second.clone()),
+                    Value::Double(2.0),
+                ]),
+            ]),
+        ]);
+        let result =
+            process_response_for_decompression(response, RequestType::ZMPop, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::BulkString(b"myzset".to_vec()),
+                Value::Array(vec![
+                    Value::Array(vec![
+                        Value::BulkString(first.as_bytes().to_vec()),
+                        Value::Double(1.5),
+                    ]),
+                    Value::Array(vec![
+                        Value::BulkString(second.as_bytes().to_vec()),
+                        Value::Double(2.0),
+                    ]),
+                ]),
+            ])
+        );
+
+        // Nothing popped
+        let result =
+            process_response_for_decompression(Value::Nil, RequestType::LMPop, Some(&manager))
+                .unwrap();
+        assert_eq!(result, Value::Nil);
+    }
+
+    #[test]
+    fn test_decompress_all() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let first = "first value for batch decompression ".repeat(10);
+        let second = "second value for batch decompression ".repeat(10);
+
+        let mut corrupted = manager.compress_value(second.as_bytes()).into_owned();
+        corrupted[HEADER_SIZE] = 0xFF;
+
+        let values = vec![
+            manager.compress_value(first.as_bytes()).into_owned(),
+            b"uncompressed".to_vec(),
+            Vec::new(),
+            manager.compress_value(second.as_bytes()).into_owned(),
+            corrupted.clone(),
+        ];
+
+        let result = manager.decompress_all(values);
+        assert_eq!(
+            result,
+            vec![
+                first.as_bytes().to_vec(),
+                b"uncompressed".to_vec(),
+                Vec::new(),
+                second.as_bytes().to_vec(),
+                corrupted, // Undecodable values fall back to the original bytes
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_output_size_stores_raw() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original_data = "A".repeat(1000);
+
+        // Without a limit the value is compressed
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let compressed = manager.compress_value(original_data.as_bytes());
+        assert!(has_magic_header(&compressed));
+        let compressed_len = compressed.len();
+
+        // A limit below the compressed size makes the manager store the value raw
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_max_output_size(Some(compressed_len - 1)),
+        )
+        .unwrap();
+        let result = manager.compress_value(original_data.as_bytes());
+        assert_eq!(result, original_data.as_bytes());
+        let result = manager
+            .compress_value_with_level(original_data.as_bytes(), Some(1))
+            .unwrap();
+        assert_eq!(result, original_data.as_bytes());
+
+        // A limit at the compressed size keeps the compressed result
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_max_output_size(Some(compressed_len)),
+        )
+        .unwrap();
+        let result = manager.compress_value(original_data.as_bytes());
+        assert!(has_magic_header(&result));
+
+        // Incompressible input stays raw regardless of the limit
+        let incompressible: Vec<u8> = (0..200u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd).with_max_output_size(Some(16)),
+        )
+        .unwrap();
+        let result = manager.compress_value(&incompressible);
+        assert_eq!(result, incompressible.as_slice());
+    }
+
+    #[test]
+    fn test_custom_command_compression_table() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let mut table = CommandCompressionTable::new();
+        table.register(
+            "MYMODULE.SET",
+            CommandCompressionBehavior::CompressValues,
+            vec![2],
+        );
+        table.register(
+            b"mymodule.get",
+            CommandCompressionBehavior::DecompressValues,
+            vec![],
+        );
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.behavior(b"mymodule.set"),
+            CommandCompressionBehavior::CompressValues
+        );
+        assert_eq!(
+            table.behavior(b"OTHER.CMD"),
+            CommandCompressionBehavior::NoCompression
+        );
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config)
+            .unwrap()
+            .with_command_table(table);
+        let value = "module command value ".repeat(10);
+
+        // MYMODULE.SET key value - the declared value index is compressed
+        let mut args = vec![
+            b"mymodule.set".to_vec(),
+            b"key".to_vec(),
+            value.as_bytes().to_vec(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::CustomCommand, Some(&manager))
+            .unwrap();
+        assert_eq!(args[0], b"mymodule.set");
+        assert_eq!(args[1], b"key");
+        assert!(has_magic_header(&args[2]));
+        let compressed = args[2].clone();
+
+        // Unregistered custom commands are untouched
+        let mut args = vec![
+            b"OTHER.SET".to_vec(),
+            b"key".to_vec(),
+            value.as_bytes().to_vec(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::CustomCommand, Some(&manager))
+            .unwrap();
+        assert_eq!(args[2], value.as_bytes());
+
+        // Responses of registered decompressing commands are decompressed
+        let result = process_custom_command_response(
+            Value::BulkString(compressed.clone()),
+            b"MYMODULE.GET",
+            &manager,
+        )
+        .unwrap();
+        assert_eq!(result, Value::BulkString(value.as_bytes().to_vec()));
+
+        let result = process_custom_command_response(
+            Value::BulkString(compressed.clone()),
+            b"OTHER.GET",
+            &manager,
+        )
+        .unwrap();
+        assert_eq!(result, Value::BulkString(compressed.clone()));
+
+        // Without a command table, replies are left alone even for registered names
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let mut result = Value::BulkString(compressed.clone());
+        process_custom_command_response_in_place(&mut result, b"MYMODULE.GET", &manager).unwrap();
+        assert_eq!(result, Value::BulkString(compressed));
+    }
+
+    #[test]
+    fn test_command_compression_table_serde() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let json = r#"{
+            "mymodule.set": { "behavior": "CompressValues", "indices": [2, 4] },
+            "MYMODULE.GET": { "behavior": "DecompressValues" }
+        }"#;
+        let table: CommandCompressionTable = serde_json::from_str(json).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.get(b"MYMODULE.SET"),
+            Some(&CommandEntry {
+                behavior: CommandCompressionBehavior::CompressValues,
+                value_indices: vec![2, 4],
+            })
+        );
+        assert_eq!(
+            table.behavior(b"mymodule.get"),
+            CommandCompressionBehavior::DecompressValues
+        );
+        assert!(table.get(b"MYMODULE.GET").unwrap().value_indices.is_empty());
+
+        // Serialization round-trips
+        let serialized = serde_json::to_string(&table).unwrap();
+        let round_tripped: CommandCompressionTable = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(round_tripped, table);
+
+        // FromIterator normalizes names like register()
+        let collected: CommandCompressionTable = vec![(
+            b"mymodule.set".to_vec(),
+            CommandEntry {
+                behavior: CommandCompressionBehavior::CompressValues,
+                value_indices: vec![2, 4],
+            },
+        )]
+        .into_iter()
+        .collect();
+        assert_eq!(collected.get(b"MYMODULE.SET"), table.get(b"MYMODULE.SET"));
+
+        // The deserialized table classifies a custom command
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap()
+        .with_command_table(table);
+        let value = "configured module value ".repeat(10);
+        let mut args = vec![
+            b"MYMODULE.SET".to_vec(),
+            b"key".to_vec(),
+            value.as_bytes().to_vec(),
+            b"option".to_vec(),
+            value.as_bytes().to_vec(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::CustomCommand, Some(&manager))
+            .unwrap();
+        assert_eq!(args[1], b"key");
+        assert!(has_magic_header(&args[2]));
+        assert_eq!(args[3], b"option");
+        assert!(has_magic_header(&args[4]));
+    }
+
+    #[test]
+    fn test_decompress_value_cow() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::borrow::Cow;
+
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let original_data = "zero copy decompression payload ".repeat(10);
+
+        // Uncompressed input is borrowed
+        let uncompressed = b"plain value";
+        let result = manager.decompress_value_cow(uncompressed).unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(result.as_ref(), uncompressed);
+
+        // Compressed input is decompressed into an owned buffer
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        let result = manager.decompress_value_cow(&compressed).unwrap();
+        assert!(matches!(result, Cow::Owned(_)));
+        assert_eq!(result.as_ref(), original_data.as_bytes());
+
+        // Disabled managers never decompress
+        let disabled =
+            CompressionManager::new(Box::new(ZstdBackend::new()), CompressionConfig::disabled())
+                .unwrap();
+        let result = disabled.decompress_value_cow(&compressed).unwrap();
+        assert!(matches!(result, Cow::Borrowed(_)));
+        assert_eq!(result.as_ref(), compressed.as_slice());
+    }
+
+    #[test]
+    fn test_recommended_workload_presets() {
+        let hints = [
+            WorkloadHint::LowLatency,
+            WorkloadHint::Balanced,
+            WorkloadHint::MaxRatio,
+            WorkloadHint::TextHeavy,
+        ];
+
+        for backend in [CompressionBackendType::Zstd, CompressionBackendType::Lz4] {
+            let presets: Vec<CompressionConfig> = hints
+                .iter()
+                .map(|hint| CompressionConfig::recommended(backend, *hint))
+                .collect();
+
+            for preset in &presets {
+                assert!(preset.enabled);
+                assert_eq!(preset.backend, backend);
+                assert!(preset.validate().is_ok());
+                // Every preset can be used to build a manager
+                assert!(CompressionManager::new(backend.make_backend(), preset.clone()).is_ok());
+            }
+
+            // Presets are distinct from each other
+            for (i, a) in presets.iter().enumerate() {
+                for b in presets.iter().skip(i + 1) {
+                    assert_ne!(a, b);
+                }
+            }
+        }
+
+        let low_latency =
+            CompressionConfig::recommended(CompressionBackendType::Zstd, WorkloadHint::LowLatency);
+        assert_eq!(low_latency.compression_level, Some(1));
+        assert_eq!(low_latency.min_compression_size, 256);
+
+        let max_ratio =
+            CompressionConfig::recommended(CompressionBackendType::Zstd, WorkloadHint::MaxRatio);
+        assert_eq!(max_ratio.compression_level, Some(19));
+        assert_eq!(max_ratio.min_compression_size, 32);
+    }
+
+    #[test]
+    fn test_scalar_values_pass_through_decompression() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let big_number =
+            redis::parse_redis_value(b"(3492890328409238509324850943850943825024385\r\n").unwrap();
+        assert!(matches!(big_number, Value::BigNumber(_)));
+
+        let scalars = vec![
+            Value::Int(42),
+            Value::Double(1.5),
+            Value::Boolean(true),
+            big_number,
+        ];
+
+        for scalar in &scalars {
+            let result = decompress_single_value_response(scalar.clone(), &manager).unwrap();
+            assert_eq!(&result, scalar);
+        }
+
+        // Scalars mixed with compressed values in an array are left untouched
+        let original_data = "member stored compressed ".repeat(10);
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        let mut elements = vec![Value::BulkString(compressed)];
+        elements.extend(scalars.iter().cloned());
+        let result = decompress_array_response(Value::Array(elements), &manager).unwrap();
+
+        let mut expected = vec![Value::BulkString(original_data.as_bytes().to_vec())];
+        expected.extend(scalars);
+        assert_eq!(result, Value::Array(expected));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_compression_tracing_spans() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        type SpanLog = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+        struct FieldRecorder<'a>(&'a mut HashMap<String, String>);
+
+        impl Visit for FieldRecorder<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .insert(field.name().to_string(), format!("{:?}", value));
+            }
+        }
+
+        struct SpanRecorder {
+            spans: SpanLog,
+        }
+
+        impl Subscriber for SpanRecorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = HashMap::new();
+                span.record(&mut FieldRecorder(&mut fields));
+                let mut spans = self.spans.lock().unwrap();
+                spans.push((span.metadata().name().to_string(), fields));
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.spans.lock().unwrap();
+                let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+                values.record(&mut FieldRecorder(fields));
+            }
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, _event: &Event<'_>) {}
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let spans: SpanLog = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = SpanRecorder {
+            spans: spans.clone(),
+        };
+
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let original_data = "traced compression payload ".repeat(10);
+
+        let compressed = tracing::subscriber::with_default(subscriber, || {
+            let compressed = manager
+                .compress_value(original_data.as_bytes())
+                .into_owned();
+            manager.decompress_value(&compressed).unwrap();
+            compressed
+        });
+
+        let spans = spans.lock().unwrap();
+        let (name, fields) = &spans[0];
+        assert_eq!(name, "compress_value");
+        assert_eq!(fields["backend"], "\"zstd\"");
+        assert_eq!(fields["level"], "Some(3)");
+        assert_eq!(fields["input_size"], original_data.len().to_string());
+        assert_eq!(fields["output_size"], compressed.len().to_string());
+
+        let (name, fields) = &spans[1];
+        assert_eq!(name, "decompress_value");
+        assert_eq!(fields["backend"], "\"zstd\"");
+        assert_eq!(fields["input_size"], compressed.len().to_string());
+        assert_eq!(fields["output_size"], original_data.len().to_string());
+    }
+
+    #[test]
+    fn test_reset_stats_returns_snapshot_and_zeroes_counters() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original_data = "stats window payload ".repeat(20);
+
+        // Scenario 1: Counters track compress, skip and decompress in the current window
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        manager.compress_value(b"tiny");
+        manager.decompress_value(&compressed).unwrap();
+
+        let stats = manager.stats();
+        assert_eq!(stats.values_compressed, 1);
+        assert_eq!(stats.values_skipped, 1);
+        assert_eq!(stats.original_bytes, original_data.len() as u64);
+        assert_eq!(stats.compressed_bytes, compressed.len() as u64);
+        assert_eq!(stats.values_decompressed, 1);
+        assert_eq!(stats.decompressed_bytes, original_data.len() as u64);
+
+        // Scenario 2: reset_stats returns the pre-reset snapshot
+        assert_eq!(manager.reset_stats(), stats);
+
+        // Scenario 3: The next window starts from zero
+        assert_eq!(manager.stats(), CompressionStats::default());
+        manager.compress_value(original_data.as_bytes());
+        assert_eq!(manager.stats().values_compressed, 1);
+        assert_eq!(manager.stats().values_skipped, 0);
+
+        // Scenario 4: Resets racing with compressions never split a compression's counters
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let compressed_len = compressed.len() as u64;
+        let windows = std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..500 {
+                        manager.compress_value(original_data.as_bytes());
+                    }
+                });
+            }
+            (0..100).map(|_| manager.reset_stats()).collect::<Vec<_>>()
+        });
+        let mut total = 0;
+        for stats in windows.into_iter().chain([manager.stats()]) {
+            assert_eq!(
+                stats.original_bytes,
+                stats.values_compressed * original_data.len() as u64
+            );
+            assert_eq!(
+                stats.compressed_bytes,
+                stats.values_compressed * compressed_len
+            );
+            total += stats.values_compressed;
+        }
+        assert_eq!(total, 2000);
+    }
+
+    #[test]
+    fn test_future_header_version_rejected() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original_data = "versioned payload ".repeat(20);
+        let backends: Vec<Box<dyn CompressionBackend>> =
+            vec![Box::new(ZstdBackend::new()), Box::new(Lz4Backend::new())];
+
+        for backend in backends {
+            let mut forged = backend.compress(original_data.as_bytes(), None).unwrap();
+            forged[HEADER_VERSION_INDEX] = MAX_SUPPORTED_VERSION + 1;
+
+            // Scenario 1: The backend refuses the forged version
+            let err = backend.decompress(&forged).unwrap_err();
+            assert!(err.to_string().contains(&format!(
+                "unsupported header version {}",
+                MAX_SUPPORTED_VERSION + 1
+            )));
+
+            // Scenario 2: Supported versions still decode
+            assert!(check_header_version(&forged).is_err());
+            forged[HEADER_VERSION_INDEX] = CURRENT_VERSION;
+            assert!(check_header_version(&forged).is_ok());
+            assert_eq!(
+                backend.decompress(&forged).unwrap(),
+                original_data.as_bytes()
+            );
+        }
+
+        // Scenario 3: The graceful manager path falls back to the stored bytes
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let mut forged = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        forged[HEADER_VERSION_INDEX] = 0xFF;
+        assert_eq!(manager.try_decompress_value(&forged), forged);
+
+        // Scenario 4: Flags version headers setting unknown flag bits are rejected too
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_integrity_mode(IntegrityMode::LengthOnly);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let mut forged = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(forged[HEADER_VERSION_INDEX], FLAGS_VERSION);
+        assert!(FrameHeader::parse(&forged).is_some());
+        forged[HEADER_FLAGS_INDEX] |= 0x80;
+        assert_eq!(KNOWN_FLAGS & 0x80, 0);
+        assert!(FrameHeader::parse(&forged).is_none());
+        let err = check_header_version(&forged).unwrap_err();
+        assert!(err.contains("unsupported header flags"));
+        assert!(manager.decompress_value(&forged).is_err());
+        assert_eq!(manager.try_decompress_value(&forged), forged);
+    }
+
+    #[test]
+    fn test_lset_and_linsert_compress_element_only() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let element = "list element payload ".repeat(20).into_bytes();
+
+        // Scenario 1: LSET key index element - only the element is compressed
+        let mut args = vec![b"mylist".to_vec(), b"3".to_vec(), element.clone()];
+        process_command_args_for_compression(&mut args, RequestType::LSet, Some(&manager)).unwrap();
+        assert_eq!(args[0], b"mylist");
+        assert_eq!(args[1], b"3");
+        assert!(has_magic_header(&args[2]));
+        let lset_element = args[2].clone();
+
+        // Scenario 2: LINSERT key BEFORE pivot element - the pivot is untouched
+        let pivot = "pivot value that is long enough to be compressed ".repeat(5);
+        let mut args = vec![
+            b"mylist".to_vec(),
+            b"BEFORE".to_vec(),
+            pivot.clone().into_bytes(),
+            element.clone(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::LInsert, Some(&manager))
+            .unwrap();
+        assert_eq!(args[0], b"mylist");
+        assert_eq!(args[1], b"BEFORE");
+        assert_eq!(args[2], pivot.as_bytes());
+        assert!(has_magic_header(&args[3]));
+
+        // Scenario 3: Elements written by LSET/LINSERT read back through LRANGE
+        let response = Value::Array(vec![
+            Value::BulkString(lset_element),
+            Value::BulkString(args[3].clone()),
+            Value::BulkString(b"plain".to_vec()),
+        ]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::LRange, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(vec![
+                Value::BulkString(element.clone()),
+                Value::BulkString(element),
+                Value::BulkString(b"plain".to_vec()),
+            ])
+        );
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_decompression_cache_hits_on_identical_input() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd).with_decompression_cache(
+            Some(DecompressionCacheConfig {
+                max_entries: 2,
+                max_bytes: 4096,
+            }),
+        );
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let original_data = "hot key payload ".repeat(50);
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+
+        // Scenario 1: The first decompression misses and populates the cache
+        assert_eq!(
+            manager.decompress_value(&compressed).unwrap(),
+            original_data.as_bytes()
+        );
+        let stats = manager.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 1, 1));
+        assert_eq!(stats.bytes, compressed.len() + original_data.len());
+
+        // Scenario 2: Identical input is served from the cache, and still counted
+        assert_eq!(
+            manager.decompress_value(&compressed).unwrap(),
+            original_data.as_bytes()
+        );
+        assert_eq!(manager.cache_stats().unwrap().hits, 1);
+        assert_eq!(manager.stats().values_decompressed, 2);
+
+        // Scenario 3: The entry bound evicts the least recently used value
+        for i in 0..2 {
+            let other = format!("other payload {} ", i).repeat(20);
+            let other_compressed = manager.compress_value(other.as_bytes()).into_owned();
+            manager.decompress_value(&other_compressed).unwrap();
+        }
+        assert_eq!(manager.cache_stats().unwrap().entries, 2);
+        manager.decompress_value(&compressed).unwrap();
+        assert_eq!(manager.cache_stats().unwrap().hits, 1);
+
+        // Scenario 4: Values larger than the byte bound are never cached
+        let large = "x".repeat(8192);
+        let large_compressed = manager.compress_value(large.as_bytes()).into_owned();
+        manager.decompress_value(&large_compressed).unwrap();
+        assert!(manager.cache_stats().unwrap().bytes <= 4096);
+
+        // Scenario 5: Managers without a configured cache report no cache stats
+        let uncached = CompressionManagerBuilder::new().build().unwrap();
+        assert!(uncached.cache_stats().is_none());
+
+        // Scenario 6: A zero entry bound is rejected
+        let config = CompressionConfig::new(CompressionBackendType::Zstd).with_decompression_cache(
+            Some(DecompressionCacheConfig {
+                max_entries: 0,
+                max_bytes: 4096,
+            }),
+        );
+        assert!(CompressionManager::new(Box::new(ZstdBackend::new()), config).is_err());
+
+        // Scenario 7: Cached values are checked again once strict mode is enabled
+        let config = CompressionConfig::new(CompressionBackendType::Zstd).with_decompression_cache(
+            Some(DecompressionCacheConfig {
+                max_entries: 2,
+                max_bytes: 4096,
+            }),
+        );
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        let legacy = ZstdBackend::new()
+            .compress(original_data.as_bytes(), None)
+            .unwrap();
+        assert_eq!(
+            manager.decompress_value(&legacy).unwrap(),
+            original_data.as_bytes()
+        );
+        manager
+            .swap_config(config.with_strict_decompress(true))
+            .unwrap();
+        let err = manager.decompress_value(&legacy).unwrap_err();
+        assert!(err.to_string().contains("declared original length"));
+    }
+
+    /// Zstd backend that claims the lz4 backend id
+    #[derive(Debug)]
+    struct MislabeledBackend(glide_core::compression::zstd_backend::ZstdBackend);
+
+    impl CompressionBackend for MislabeledBackend {
+        fn compress(&self, data: &[u8], level: Option<i32>) -> CompressionResult<Vec<u8>> {
+            self.0.compress(data, level)
+        }
+        fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+            self.0.decompress(data)
+        }
+        fn is_compressed(&self, data: &[u8]) -> bool {
+            self.0.is_compressed(data)
+        }
+        fn backend_name(&self) -> &'static str {
+            "zstd"
+        }
+        fn default_level(&self) -> Option<i32> {
+            self.0.default_level()
+        }
+        fn backend_id(&self) -> u8 {
+            CompressionBackendType::Lz4.backend_id()
+        }
+        fn supports_level(&self, level: i32) -> bool {
+            self.0.supports_level(level)
+        }
+        fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()> {
+            self.0.validate_compression_level(level)
+        }
+    }
+
+    #[test]
+    fn test_assert_backend_consistency_accepts_builtin_backends() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        assert_backend_consistency(&ZstdBackend::new());
+        assert_backend_consistency(&Lz4Backend::new());
+        assert_eq!(ZstdBackend::new().name_and_id(), ("zstd", 0x01));
+        assert_eq!(Lz4Backend::new().name_and_id(), ("lz4", 0x02));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "backend 'zstd' reports id 2")]
+    fn test_inconsistent_backend_rejected_in_debug_builds() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        // The id matches the lz4 config, so only the consistency assertion can catch it
+        let _ = CompressionManager::new(
+            Box::new(MislabeledBackend(ZstdBackend::new())),
+            CompressionConfig::new(CompressionBackendType::Lz4),
+        );
+    }
+
+    #[test]
+    fn test_extract_recorded_compression_level() {
+        let original_data = "audited payload ".repeat(20);
+
+        // Scenario 1: The explicit level is recorded and read back
+        let manager = CompressionManagerBuilder::new().level(9).build().unwrap();
+        let config = manager.config().as_ref().clone().with_record_level(true);
+        let manager = CompressionManager::new(config.backend.make_backend(), config).unwrap();
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(extract_level(&compressed), Some(9));
+        assert_eq!(FrameHeader::parse(&compressed).unwrap().flags, FLAG_LEVEL);
+        assert_eq!(
+            manager.decompress_value(&compressed).unwrap(),
+            original_data.as_bytes()
+        );
+
+        // Scenario 2: The level is stored after the integrity fields and negative levels survive
+        let config = CompressionConfig::new(CompressionBackendType::Lz4)
+            .with_compression_level(Some(-5))
+            .with_integrity_mode(IntegrityMode::Crc32)
+            .with_record_level(true);
+        let manager = CompressionManager::new(config.backend.make_backend(), config).unwrap();
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(extract_level(&compressed), Some(-5));
+        assert_eq!(
+            manager.decompress_value(&compressed).unwrap(),
+            original_data.as_bytes()
+        );
+
+        // Scenario 3: The level is absent unless recording is enabled
+        let manager = CompressionManagerBuilder::new().level(9).build().unwrap();
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(extract_level(&compressed), None);
+        assert_eq!(extract_level(b"not compressed"), None);
+    }
+
+    #[test]
+    fn test_in_place_response_decompression() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let element = "in place element ".repeat(20).into_bytes();
+        let compressed = manager.compress_value(&element).into_owned();
+
+        // Scenario 1: Array elements are decompressed in the original value
+        let mut response = Value::Array(vec![
+            Value::BulkString(compressed.clone()),
+            Value::BulkString(b"plain".to_vec()),
+            Value::Nil,
+        ]);
+        process_response_for_decompression_in_place(
+            &mut response,
+            RequestType::LRange,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            Value::Array(vec![
+                Value::BulkString(element.clone()),
+                Value::BulkString(b"plain".to_vec()),
+                Value::Nil,
+            ])
+        );
+
+        // Scenario 2: The owned variant produces the same result
+        let owned = process_response_for_decompression(
+            Value::Array(vec![
+                Value::BulkString(compressed.clone()),
+                Value::BulkString(b"plain".to_vec()),
+                Value::Nil,
+            ]),
+            RequestType::LRange,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(owned, response);
+
+        // Scenario 3: Nested ZMPOP members are decompressed, scores untouched
+        let mut response = Value::Array(vec![
+            Value::BulkString(b"zkey".to_vec()),
+            Value::Array(vec![Value::Array(vec![
+                Value::BulkString(compressed.clone()),
+                Value::Double(1.5),
+            ])]),
+        ]);
+        process_response_for_decompression_in_place(
+            &mut response,
+            RequestType::ZMPop,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            Value::Array(vec![
+                Value::BulkString(b"zkey".to_vec()),
+                Value::Array(vec![Value::Array(vec![
+                    Value::BulkString(element),
+                    Value::Double(1.5),
+                ])]),
+            ])
+        );
+
+        // Scenario 4: Commands that don't decompress leave the value untouched
+        let mut response = Value::BulkString(compressed.clone());
+        process_response_for_decompression_in_place(
+            &mut response,
+            RequestType::ObjectEncoding,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(compressed));
+    }
+
+    #[test]
+    fn test_set_with_get_option_round_trip() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let old_value = "previous value ".repeat(20).into_bytes();
+        let new_value = "replacement value ".repeat(20).into_bytes();
+        let stored_old = manager.compress_value(&old_value).into_owned();
+
+        // Scenario 1: SET key newval GET compresses the new value, leaving the option alone
+        let mut args = vec![
+            b"key".to_vec(),
+            new_value.clone(),
+            b"EX".to_vec(),
+            b"10".to_vec(),
+            b"get".to_vec(),
+        ];
+        assert!(set_returns_old_value(&args));
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert!(has_magic_header(&args[1]));
+        assert_eq!(args[4], b"get");
+        assert_eq!(manager.decompress_value(&args[1]).unwrap(), new_value);
+
+        // Scenario 2: The previous value in the reply is decompressed
+        let response = process_response_for_decompression(
+            Value::BulkString(stored_old),
+            RequestType::Set,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(old_value));
+
+        // Scenario 3: A missing previous value and the plain OK reply are untouched
+        let response =
+            process_response_for_decompression(Value::Nil, RequestType::Set, Some(&manager))
+                .unwrap();
+        assert_eq!(response, Value::Nil);
+        let response =
+            process_response_for_decompression(Value::Okay, RequestType::Set, Some(&manager))
+                .unwrap();
+        assert_eq!(response, Value::Okay);
+
+        // Scenario 4: Only option positions are checked for GET, never the key or value
+        assert!(!set_returns_old_value([
+            b"GET".as_slice(),
+            b"GET".as_slice()
+        ]));
+        assert!(!set_returns_old_value([
+            b"key".as_slice(),
+            b"value".as_slice(),
+            b"NX".as_slice()
+        ]));
+    }
+
+    #[test]
+    fn test_swap_config_under_concurrent_compression() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let manager = Arc::new(CompressionManagerBuilder::new().build().unwrap());
+        let original_data = Arc::new("hot swapped payload ".repeat(50).into_bytes());
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Scenario 1: Invalid configurations are rejected and the current one is kept
+        let lz4 = CompressionConfig::new(CompressionBackendType::Lz4);
+        assert!(manager.swap_config(lz4).is_err());
+        let invalid_level =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_compression_level(Some(999));
+        assert!(manager.swap_config(invalid_level).is_err());
+        assert_eq!(manager.config().compression_level, Some(3));
+
+        // Scenario 2: Compressing threads always produce valid output while the config changes
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let manager = manager.clone();
+                let original_data = original_data.clone();
+                let done = done.clone();
+                std::thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        let output = manager.compress_value(&original_data);
+                        assert_eq!(
+                            manager.decompress_value(&output).unwrap(),
+                            original_data.as_slice()
+                        );
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..200 {
+            let config = CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_compression_level(Some(1 + i % 19))
+                .with_integrity_mode(if i % 2 == 0 {
+                    IntegrityMode::Crc32
+                } else {
+                    IntegrityMode::None
+                })
+                .with_min_compression_size(if i % 3 == 0 { 4096 } else { 64 });
+            manager.swap_config(config).unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // Scenario 3: The last swapped configuration is in effect
+        let config = manager.config();
+        assert_eq!(config.compression_level, Some(1 + 199 % 19));
+        assert_eq!(config.integrity_mode, IntegrityMode::None);
+    }
+
+    #[test]
+    fn test_diagnostic_commands_never_decompressed() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        // Output that coincidentally starts with a valid compressed frame
+        let lookalike = manager
+            .compress_value("# Server\r\nredis_version:8.0.0\r\n".repeat(10).as_bytes())
+            .into_owned();
+        assert!(has_magic_header(&lookalike));
+
+        // Scenario 1: INFO and CLIENT INFO replies are returned verbatim
+        for request_type in [RequestType::Info, RequestType::ClientInfo] {
+            assert!(request_type.never_decompresses());
+            let response = process_response_for_decompression(
+                Value::BulkString(lookalike.clone()),
+                request_type,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(response, Value::BulkString(lookalike.clone()));
+        }
+        assert!(!RequestType::Get.never_decompresses());
+
+        // Scenario 2: Registering diagnostic commands in the table doesn't override the allowlist
+        let mut table = CommandCompressionTable::new();
+        for name in ["info", "DEBUG", "client"] {
+            table.register(name, CommandCompressionBehavior::DecompressValues, vec![]);
+        }
+        table.register(
+            "MY.GET",
+            CommandCompressionBehavior::DecompressValues,
+            vec![],
+        );
+        let manager = manager.with_command_table(table);
+        for name in [b"INFO".as_slice(), b"debug", b"CLIENT"] {
+            assert!(is_never_decompressed_command(name));
+            let response = process_custom_command_response(
+                Value::BulkString(lookalike.clone()),
+                name,
+                &manager,
+            )
+            .unwrap();
+            assert_eq!(response, Value::BulkString(lookalike.clone()));
+        }
+
+        // Scenario 3: Other registered commands are still decompressed
+        let response = process_custom_command_response(
+            Value::BulkString(lookalike.clone()),
+            b"MY.GET",
+            &manager,
+        )
+        .unwrap();
+        assert_ne!(response, Value::BulkString(lookalike));
+    }
+
+    #[test]
+    fn test_try_compress_value_reported() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+
+        // Scenario 1: A compressible value is reported as compressed
+        let original_data = "reported payload ".repeat(20);
+        let (output, compressed) = manager.try_compress_value_reported(original_data.as_bytes());
+        assert!(compressed);
+        assert!(has_magic_header(&output));
+        assert_eq!(
+            manager.decompress_value(&output).unwrap(),
+            original_data.as_bytes()
+        );
+
+        // Scenario 2: A below-threshold value is returned raw
+        let (output, compressed) = manager.try_compress_value_reported(b"short");
+        assert!(!compressed);
+        assert_eq!(output, b"short");
+    }
+
+    #[cfg(feature = "bytes-api")]
+    #[test]
+    fn test_bytes_round_trip() {
+        use bytes::{Bytes, BytesMut};
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+
+        // Scenario 1: Compressible values round-trip through Bytes
+        let mut buffer = BytesMut::new();
+        buffer.extend_from_slice("bytes payload ".repeat(20).as_bytes());
+        let original = buffer.freeze();
+        let compressed = manager.compress_value_bytes(&original).unwrap();
+        assert!(has_magic_header(&compressed));
+        assert_eq!(
+            manager.decompress_value_bytes(&compressed).unwrap(),
+            original
+        );
+
+        // Scenario 2: Untransformed values share the input buffer
+        let short = Bytes::from_static(b"short");
+        let output = manager.compress_value_bytes(&short).unwrap();
+        assert_eq!(output.as_ptr(), short.as_ptr());
+        let output = manager.decompress_value_bytes(&original).unwrap();
+        assert_eq!(output.as_ptr(), original.as_ptr());
+
+        // Scenario 3: Corrupted compressed data is reported as an error
+        let mut corrupted = BytesMut::from(&compressed[..]);
+        corrupted.truncate(HEADER_SIZE + 2);
+        assert!(manager.decompress_value_bytes(&corrupted.freeze()).is_err());
+    }
+
+    #[test]
+    fn test_min_savings_bytes_stores_raw() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        // Scenario 1: Saving 3 bytes with a 10 byte threshold is not worth keeping
+        let config =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_min_savings_bytes(Some(10));
+        assert!(!config.should_keep_compressed(100, 97));
+        assert!(config.should_keep_compressed(100, 90));
+        assert!(
+            CompressionConfig::new(CompressionBackendType::Zstd).should_keep_compressed(100, 97)
+        );
+
+        // Scenario 2: The manager stores values raw when the savings fall short
+        let original_data = "savings payload ".repeat(20);
+        let plain = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let compressed = plain.compress_value(original_data.as_bytes());
+        let savings = original_data.len() - compressed.len();
+
+        let strict = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            config.with_min_savings_bytes(Some(savings + 1)),
+        )
+        .unwrap();
+        let output = strict.compress_value(original_data.as_bytes());
+        assert_eq!(output.as_ref(), original_data.as_bytes());
+
+        let exact = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_min_savings_bytes(Some(savings)),
+        )
+        .unwrap();
+        assert!(has_magic_header(
+            &exact.compress_value(original_data.as_bytes())
+        ));
+    }
+
+    #[test]
+    fn test_compiled_backends() {
+        let backends = compiled_backends();
+        assert_eq!(
+            backends.contains(&CompressionBackendType::Zstd),
+            cfg!(any(feature = "zstd", feature = "wasm"))
+        );
+        assert_eq!(
+            backends.contains(&CompressionBackendType::Lz4),
+            cfg!(feature = "lz4")
+        );
+
+        // Every compiled backend passes configuration validation
+        for backend in backends {
+            assert!(CompressionConfig::new(backend).validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_geosearch_member_decompression() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let member = "geo member name ".repeat(10).into_bytes();
+        let compressed = manager.compress_value(&member).into_owned();
+
+        // Scenario 1: Plain GEOSEARCH replies are a list of member names
+        let response = Value::Array(vec![
+            Value::BulkString(compressed.clone()),
+            Value::BulkString(b"Palermo".to_vec()),
+        ]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::GeoSearch, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(vec![
+                Value::BulkString(member.clone()),
+                Value::BulkString(b"Palermo".to_vec()),
+            ])
+        );
+
+        // Scenario 2: WITHDIST WITHHASH WITHCOORD entries only decompress the member
+        let entry = |name: Vec<u8>| {
+            Value::Array(vec![
+                Value::BulkString(name),
+                Value::BulkString(b"190.4424".to_vec()),
+                Value::Int(3479099956230698),
+                Value::Array(vec![Value::Double(13.361389), Value::Double(38.115556)]),
+            ])
+        };
+        let response = Value::Array(vec![entry(compressed), entry(b"Catania".to_vec())]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::GeoSearch, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(vec![entry(member), entry(b"Catania".to_vec())])
+        );
+    }
+
+    #[test]
+    fn test_key_argument_never_compressed() {
+        let long_key = "key that is long enough to be compressed ".repeat(5);
+        let value = "value that is long enough to be compressed ".repeat(5);
+
+        // Scenario 1: A custom mapping listing the key position leaves the key intact
+        let mut table = CommandCompressionTable::new();
+        table.register(
+            "MYMODULE.PUT",
+            CommandCompressionBehavior::CompressValues,
+            vec![0, 1, 2],
+        );
+        let manager = CompressionManagerBuilder::new()
+            .build()
+            .unwrap()
+            .with_command_table(table);
+        let mut args = vec![
+            b"MYMODULE.PUT".to_vec(),
+            long_key.clone().into_bytes(),
+            value.clone().into_bytes(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::CustomCommand, Some(&manager))
+            .unwrap();
+        assert_eq!(args[0], b"MYMODULE.PUT");
+        assert_eq!(args[1], long_key.as_bytes());
+        assert!(has_magic_header(&args[2]));
+
+        // Scenario 2: Built-in commands keep their key even when it is compressible
+        let mut args = vec![long_key.clone().into_bytes(), value.into_bytes()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert_eq!(args[0], long_key.as_bytes());
+        assert!(has_magic_header(&args[1]));
+    }
+
+    #[test]
+    fn test_error_categories() {
+        assert_eq!(
+            CompressionError::compression_failed("zstd", Some(3), 10, "test").category(),
+            ErrorCategory::Codec
+        );
+        assert_eq!(
+            CompressionError::decompression_failed("zstd", 10, "test").category(),
+            ErrorCategory::Codec
+        );
+        assert_eq!(
+            CompressionError::invalid_configuration("zstd", "test").category(),
+            ErrorCategory::Config
+        );
+        assert_eq!(
+            CompressionError::unsupported_backend("brotli").category(),
+            ErrorCategory::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_decompress_or_strip() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original_data = "recoverable payload ".repeat(20);
+
+        // Scenario 1: Valid compressed data is decompressed
+        let compressed = manager
+            .compress_value(original_data.as_bytes())
+            .into_owned();
+        assert_eq!(
+            manager.decompress_or_strip(&compressed),
+            original_data.as_bytes()
+        );
+
+        // Scenario 2: A valid header with a corrupt payload returns the payload
+        let mut corrupt = create_header(CompressionBackendType::Zstd.backend_id()).to_vec();
+        corrupt.extend_from_slice(b"not a zstd frame");
+        assert!(manager.decompress_value(&corrupt).is_err());
+        assert_eq!(manager.decompress_or_strip(&corrupt), b"not a zstd frame");
+
+        // Scenario 3: A truncated flags header strips the fixed header
+        let mut truncated =
+            create_header_with_version(CompressionBackendType::Zstd.backend_id(), FLAGS_VERSION)
+                .to_vec();
+        truncated.push(FLAG_ORIGINAL_LENGTH | FLAG_CRC32);
+        truncated.extend_from_slice(b"xy");
+        assert_eq!(
+            manager.decompress_or_strip(&truncated),
+            [FLAG_ORIGINAL_LENGTH | FLAG_CRC32, b'x', b'y']
+        );
+
+        // Scenario 4: Uncompressed data is returned as-is
+        assert_eq!(manager.decompress_or_strip(b"plain"), b"plain");
+    }
+
+    #[test]
+    fn test_no_compress_sentinel() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_no_compress_sentinel(b"\xffRAW".to_vec());
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let payload = "application payload ".repeat(20);
+
+        // Scenario 1: Sentinel-prefixed values are stored as-is, sentinel included
+        let mut opted_out = b"\xffRAW".to_vec();
+        opted_out.extend_from_slice(payload.as_bytes());
+        assert!(!manager.should_compress(&opted_out));
+        assert_eq!(
+            manager.compress_value(&opted_out).as_ref(),
+            opted_out.as_slice()
+        );
+        assert_eq!(
+            manager
+                .compress_value_with_level(&opted_out, Some(9))
+                .unwrap(),
+            opted_out
+        );
+
+        // Scenario 2: Other values are still compressed
+        assert!(manager.should_compress(payload.as_bytes()));
+        assert!(has_magic_header(
+            &manager.compress_value(payload.as_bytes())
+        ));
+
+        // Scenario 3: An empty sentinel is rejected
+        let config =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_no_compress_sentinel(vec![]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_dump_payload_returned_verbatim() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        // A DUMP of a compressed string embeds our frame inside the server serialization
+        let compressed = manager
+            .compress_value("dumped value ".repeat(20).as_bytes())
+            .into_owned();
+        let mut dump_payload = compressed.clone();
+        dump_payload
+            .extend_from_slice(&[0x0b, 0x00, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0]);
+
+        // The DUMP reply is never decompressed, even when it starts with the magic bytes
+        assert!(RequestType::Dump.never_decompresses());
+        let response = process_response_for_decompression(
+            Value::BulkString(dump_payload.clone()),
+            RequestType::Dump,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(dump_payload.clone()));
+
+        // RESTORE arguments are never compressed
+        let mut args = vec![b"key".to_vec(), b"0".to_vec(), dump_payload.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Restore, Some(&manager))
+            .unwrap();
+        assert_eq!(args[2], dump_payload);
+
+        // Custom DUMP commands are also returned verbatim
+        assert!(is_never_decompressed_command(b"dump"));
+    }
+
+    /// Backend that reports itself as zstd but always fails to compress
+    #[derive(Debug)]
+    struct FailingBackend(glide_core::compression::zstd_backend::ZstdBackend);
+
+    impl CompressionBackend for FailingBackend {
+        fn compress(&self, data: &[u8], level: Option<i32>) -> CompressionResult<Vec<u8>> {
+            Err(CompressionError::compression_failed(
+                self.backend_name(),
+                level,
+                data.len(),
+                "rigged to fail",
+            ))
+        }
+        fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+            self.0.decompress(data)
+        }
+        fn is_compressed(&self, data: &[u8]) -> bool {
+            self.0.is_compressed(data)
+        }
+        fn backend_name(&self) -> &'static str {
+            self.0.backend_name()
+        }
+        fn default_level(&self) -> Option<i32> {
+            self.0.default_level()
+        }
+        fn backend_id(&self) -> u8 {
+            self.0.backend_id()
+        }
+        fn supports_level(&self, level: i32) -> bool {
+            self.0.supports_level(level)
+        }
+        fn validate_compression_level(&self, level: Option<i32>) -> CompressionResult<()> {
+            self.0.validate_compression_level(level)
+        }
+    }
+
+    #[test]
+    fn test_on_fallback_hook() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+        use std::sync::{Arc, Mutex};
+
+        let errors = Arc::new(Mutex::new(Vec::<CompressionError>::new()));
+        let recorded = errors.clone();
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_on_fallback(move |error| recorded.lock().unwrap().push(error.clone()));
+        let manager =
+            CompressionManager::new(Box::new(FailingBackend(ZstdBackend::new())), config).unwrap();
+        let original_data = "fallback payload ".repeat(20);
+
+        // Scenario 1: A failing compression falls back to the original and reports the error
+        let output = manager.compress_value(original_data.as_bytes());
+        assert_eq!(output.as_ref(), original_data.as_bytes());
+        {
+            let errors = errors.lock().unwrap();
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(
+                &errors[0],
+                CompressionError::CompressionFailed { reason, .. } if reason == "rigged to fail"
+            ));
+        }
+
+        // Scenario 2: Decompression fallbacks, including in responses, are reported
+        let mut corrupt = create_header(CompressionBackendType::Zstd.backend_id()).to_vec();
+        corrupt.extend_from_slice(b"garbage");
+        assert_eq!(manager.try_decompress_value(&corrupt), corrupt);
+        let response = process_response_for_decompression(
+            Value::BulkString(corrupt.clone()),
+            RequestType::Get,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(corrupt));
+        {
+            let errors = errors.lock().unwrap();
+            assert_eq!(errors.len(), 3);
+            assert!(
+                errors[1..]
+                    .iter()
+                    .all(|error| matches!(error, CompressionError::DecompressionFailed { .. }))
+            );
+        }
+
+        // Scenario 3: Below-threshold skips are not fallbacks
+        manager.compress_value(b"tiny");
+        assert_eq!(errors.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_lpush_and_lindex_round_trip() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let element = "pushed element ".repeat(20).into_bytes();
+
+        // Scenario 1: Every pushed element is compressed, the key is untouched
+        for request_type in [RequestType::LPush, RequestType::RPush] {
+            let mut args = vec![
+                b"mylist".to_vec(),
+                element.clone(),
+                b"small".to_vec(),
+                element.clone(),
+            ];
+            process_command_args_for_compression(&mut args, request_type, Some(&manager)).unwrap();
+            assert_eq!(args[0], b"mylist");
+            assert!(has_magic_header(&args[1]));
+            assert_eq!(args[2], b"small");
+            assert!(has_magic_header(&args[3]));
+        }
+
+        // Scenario 2: LINDEX reads a pushed element back decompressed
+        let stored = manager.compress_value(&element).into_owned();
+        let response = process_response_for_decompression(
+            Value::BulkString(stored.clone()),
+            RequestType::LIndex,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(element.clone()));
+
+        // Scenario 3: An out of range index returns nil
+        let response =
+            process_response_for_decompression(Value::Nil, RequestType::LIndex, Some(&manager))
+                .unwrap();
+        assert_eq!(response, Value::Nil);
+
+        // Scenario 4: LPOP|RPOP return one element, or an array of them with a count
+        for request_type in [RequestType::LPop, RequestType::RPop] {
+            let response = process_response_for_decompression(
+                Value::BulkString(stored.clone()),
+                request_type,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(response, Value::BulkString(element.clone()));
+
+            let response = process_response_for_decompression(
+                Value::Array(vec![
+                    Value::BulkString(stored.clone()),
+                    Value::BulkString(b"small".to_vec()),
+                ]),
+                request_type,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(
+                response,
+                Value::Array(vec![
+                    Value::BulkString(element.clone()),
+                    Value::BulkString(b"small".to_vec()),
+                ])
+            );
+        }
+
+        // Scenario 5: The moving commands return the moved element, nil on an empty source
+        for request_type in [
+            RequestType::LMove,
+            RequestType::BLMove,
+            RequestType::RPopLPush,
+            RequestType::BRPopLPush,
+        ] {
+            let response = process_response_for_decompression(
+                Value::BulkString(stored.clone()),
+                request_type,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(response, Value::BulkString(element.clone()));
+            let response =
+                process_response_for_decompression(Value::Nil, request_type, Some(&manager))
+                    .unwrap();
+            assert_eq!(response, Value::Nil);
+        }
+    }
+
+    #[test]
+    fn test_preflight_plan() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let large_value = "preflight payload ".repeat(100).into_bytes();
+
+        // Scenario 1: SET with a large value plans index 1 with a bound
+        let args = vec![b"key".to_vec(), large_value.clone()];
+        let plan = manager.preflight(&args, RequestType::Set);
+        assert_eq!(plan.values.len(), 1);
+        let planned = plan.values[0];
+        assert_eq!(planned.index, 1);
+        assert_eq!(planned.input_size, large_value.len());
+        assert!(planned.max_output_size > large_value.len());
+        // The arguments are untouched and the real output respects the bound
+        assert_eq!(args[1], large_value);
+        let compressed = manager.compress_value(&large_value);
+        assert!(compressed.len() <= planned.max_output_size);
+
+        // Scenario 2: Small values, other commands and disabled managers plan nothing
+        assert!(
+            manager
+                .preflight(&[b"key".to_vec(), b"v".to_vec()], RequestType::Set)
+                .is_empty()
+        );
+        assert!(manager.preflight(&args, RequestType::Get).is_empty());
+
+        // Scenario 3: Custom command indices include the command name
+        let mut table = CommandCompressionTable::new();
+        table.register(
+            "MY.SET",
+            CommandCompressionBehavior::CompressValues,
+            vec![2],
+        );
+        let manager = manager.with_command_table(table);
+        let args = vec![b"MY.SET".to_vec(), b"key".to_vec(), large_value.clone()];
+        let plan = manager.preflight(&args, RequestType::CustomCommand);
+        assert_eq!(
+            plan.values.iter().map(|v| v.index).collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        // Scenario 4: Bounds account for optional header fields and hold for lz4 too
+        let config = CompressionConfig::new(CompressionBackendType::Lz4)
+            .with_integrity_mode(IntegrityMode::Crc32)
+            .with_record_level(true);
+        let manager = CompressionManager::new(Box::new(Lz4Backend::new()), config).unwrap();
+        let incompressible: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        let bound = manager.max_compressed_bound(incompressible.len());
+        let backend_output = Lz4Backend::new().compress(&incompressible, None).unwrap();
+        assert!(backend_output.len() - HEADER_SIZE + 10 <= bound);
+    }
+
+    #[test]
+    fn test_empty_values_never_framed() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        // Scenario 1: Even a config bypassing the minimum size never compresses empty values
+        let mut config = CompressionConfig::new(CompressionBackendType::Zstd);
+        config.min_compression_size = 0;
+        assert!(!config.should_compress(0));
+        assert!(config.should_compress(1));
+
+        // Scenario 2: An empty SET value is stored raw
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd)
+                .with_min_compression_size(MIN_COMPRESSED_SIZE),
+        )
+        .unwrap();
+        let mut args = vec![b"key".to_vec(), Vec::new()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert!(args[1].is_empty());
+        assert!(manager.compress_value(b"").is_empty());
+        assert!(
+            manager
+                .compress_value_with_level(b"", Some(1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_backend_type_by_id() {
+        for backend in CompressionBackendType::all() {
+            assert_eq!(
+                CompressionBackendType::by_id(backend.backend_id()),
+                Some(backend)
+            );
+        }
+        assert_eq!(CompressionBackendType::by_id(0x00), None);
+        assert_eq!(CompressionBackendType::by_id(0xFF), None);
+    }
+
+    /// Compresses only values stored under keys containing "blob", whatever their size
+    #[derive(Debug)]
+    struct BlobKeysPolicy;
+
+    impl CompressionPolicy for BlobKeysPolicy {
+        fn should_compress(&self, key: &[u8], _value: &[u8], _request_type: RequestType) -> bool {
+            key.windows(4).any(|window| window == b"blob")
+        }
+    }
+
+    #[test]
+    fn test_custom_compression_policy() {
+        let manager = CompressionManagerBuilder::new()
+            .min_size(1024)
+            .build()
+            .unwrap()
+            .with_policy(Box::new(BlobKeysPolicy));
+        let value = "policy driven payload ".repeat(10).into_bytes();
+        assert!(value.len() < 1024);
+
+        // Scenario 1: Keys containing "blob" are compressed, even below the size threshold
+        let mut args = vec![b"user:blob:1".to_vec(), value.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert!(has_magic_header(&args[1]));
+        assert_eq!(
+            manager
+                .preflight(&[b"user:blob:1".to_vec(), value.clone()], RequestType::Set)
+                .values
+                .len(),
+            1
+        );
+
+        // Scenario 2: Other keys are never compressed, even above the size threshold
+        let large_value = "policy driven payload ".repeat(100).into_bytes();
+        let mut args = vec![b"user:profile:1".to_vec(), large_value.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert_eq!(args[1], large_value);
+        assert!(manager.preflight(&args, RequestType::Set).is_empty());
+
+        // Scenario 3: Without a policy the size-based logic applies
+        let manager = CompressionManagerBuilder::new()
+            .min_size(1024)
+            .build()
+            .unwrap();
+        let mut args = vec![b"user:blob:1".to_vec(), value.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert_eq!(args[1], value);
+    }
+
+    #[test]
+    fn test_exec_response_decompression() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let value = "transaction value ".repeat(20).into_bytes();
+        let compressed = manager.compress_value(&value).into_owned();
+
+        // MULTI; GET a; DEL a; GET b; LRANGE l 0 -1; EXEC
+        let results = vec![
+            Value::BulkString(compressed.clone()),
+            Value::Int(1),
+            Value::Nil,
+            Value::Array(vec![Value::BulkString(compressed.clone())]),
+        ];
+        let request_types = [
+            RequestType::Get,
+            RequestType::Del,
+            RequestType::Get,
+            RequestType::LRange,
+        ];
+        let decompressed = process_exec_response(results, &request_types, Some(&manager)).unwrap();
+        assert_eq!(
+            decompressed,
+            vec![
+                Value::BulkString(value.clone()),
+                Value::Int(1),
+                Value::Nil,
+                Value::Array(vec![Value::BulkString(value)]),
+            ]
+        );
+
+        // Results of commands that don't decompress, or without a request type, are untouched
+        let results = vec![
+            Value::BulkString(compressed.clone()),
+            Value::BulkString(compressed.clone()),
+        ];
+        let untouched =
+            process_exec_response(results.clone(), &[RequestType::Dump], Some(&manager)).unwrap();
+        assert_eq!(untouched, results);
+    }
+
+    #[test]
+    fn test_multi_frame_values() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd).with_multi_frame(true);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+
+        // Scenario 1: Frames record their payload length
+        let first = "first appended chunk ".repeat(10).into_bytes();
+        let second = "second appended chunk ".repeat(10).into_bytes();
+        let first_frame = manager.compress_value(&first).into_owned();
+        let second_frame = manager.compress_value(&second).into_owned();
+        let (header, payload) = split_frame(&first_frame).unwrap();
+        assert_eq!(header.flags & FLAG_FRAME_LENGTH, FLAG_FRAME_LENGTH);
+        assert_eq!(header.frame_length, Some(payload.len() as u32));
+        assert_eq!(header.encoded_len() + payload.len(), first_frame.len());
+
+        // Scenario 2: Concatenated frames decompress into the joined original
+        let mut appended = first_frame.clone();
+        appended.extend_from_slice(&second_frame);
+        let mut expected = first.clone();
+        expected.extend_from_slice(&second);
+        assert_eq!(manager.decompress_value(&appended).unwrap(), expected);
+
+        // Scenario 3: A truncated trailing frame is rejected
+        let truncated = &appended[..appended.len() - 1];
+        assert!(ZstdBackend::new().decompress(truncated).is_err());
+
+        // Scenario 4: Single frames remain readable by clients without multi-frame enabled
+        let plain = CompressionManagerBuilder::new().build().unwrap();
+        assert_eq!(plain.decompress_value(&first_frame).unwrap(), first);
+    }
+
+    #[test]
+    fn test_read_backends_during_migration() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let legacy = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Zstd)
+            .build()
+            .unwrap();
+        let manager = CompressionManager::new(
+            Box::new(Lz4Backend::new()),
+            CompressionConfig::new(CompressionBackendType::Lz4),
+        )
+        .unwrap()
+        .with_read_backends(vec![Box::new(ZstdBackend::new())]);
+
+        // Scenario 1: New values are written with the primary backend
+        let value = "value written during migration ".repeat(10).into_bytes();
+        let written = manager.compress_value(&value).into_owned();
+        assert_eq!(
+            extract_backend_id(&written),
+            Some(CompressionBackendType::Lz4.backend_id())
+        );
+        assert_eq!(manager.decompress_value(&written).unwrap(), value);
+
+        // Scenario 2: Legacy zstd values are read through the read backend
+        let old_value = "value written before migration ".repeat(10).into_bytes();
+        let old_written = legacy.compress_value(&old_value).into_owned();
+        assert_eq!(
+            extract_backend_id(&old_written),
+            Some(CompressionBackendType::Zstd.backend_id())
+        );
+        assert_eq!(manager.decompress_value(&old_written).unwrap(), old_value);
+        assert_eq!(manager.stats().values_decompressed, 2);
+    }
+
+    #[test]
+    fn test_is_glide_compressed_boundaries() {
+        let header = create_header(CompressionBackendType::Zstd.backend_id());
+
+        // Scenario 1: Too short to hold a header and a payload
+        assert!(!is_glide_compressed(&[]));
+        assert!(!is_glide_compressed(&header[..3]));
+        assert!(!is_glide_compressed(&header));
+        assert_eq!(header.len(), MIN_COMPRESSED_SIZE - 1);
+
+        // Scenario 2: Header followed by at least one payload byte
+        let mut data = header.to_vec();
+        data.push(0);
+        assert_eq!(data.len(), MIN_COMPRESSED_SIZE);
+        assert!(is_glide_compressed(&data));
+        data.push(0);
+        assert!(is_glide_compressed(&data));
+
+        // Scenario 3: Wrong magic prefix at the minimum length
+        data.truncate(MIN_COMPRESSED_SIZE);
+        data[0] = 0xff;
+        assert!(!is_glide_compressed(&data));
+
+        // Scenario 4: Values rejected by the pre-filter are passed through untouched
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        assert_eq!(manager.decompress_value(&header).unwrap(), header.to_vec());
+    }
+
+    /// Backend writing the value as ASCII hex after the header, so its output is always UTF-8
+    #[derive(Debug)]
+    struct HexBackend;
+
+    impl CompressionBackend for HexBackend {
+        fn compress(&self, data: &[u8], _level: Option<i32>) -> CompressionResult<Vec<u8>> {
+            let mut framed = create_header(self.backend_id()).to_vec();
+            framed.extend(
+                data.iter()
+                    .flat_map(|byte| format!("{:02x}", byte).into_bytes()),
+            );
+            Ok(framed)
+        }
+        fn decompress(&self, data: &[u8]) -> CompressionResult<Vec<u8>> {
+            let hex = std::str::from_utf8(&data[HEADER_SIZE..]).unwrap();
+            Ok((0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect())
+        }
+        fn is_compressed(&self, data: &[u8]) -> bool {
+            extract_backend_id(data) == Some(self.backend_id())
+        }
+        fn backend_name(&self) -> &'static str {
+            "hex"
+        }
+        fn default_level(&self) -> Option<i32> {
+            None
+        }
+        fn backend_id(&self) -> u8 {
+            CompressionBackendType::Zstd.backend_id()
+        }
+        fn supports_level(&self, _level: i32) -> bool {
+            false
+        }
+        fn validate_compression_level(&self, _level: Option<i32>) -> CompressionResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_simple_string_decompression_types() {
+        use redis::Value;
+
+        let manager = CompressionManager::new(
+            Box::new(HexBackend),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let encode = |data: &[u8]| {
+            let compressed = HexBackend.compress(data, None).unwrap();
+            Value::SimpleString(String::from_utf8(compressed).unwrap())
+        };
+
+        // Scenario 1: UTF-8 content stays a SimpleString
+        let text = "simple string content ".repeat(10);
+        let decompressed =
+            decompress_single_value_response(encode(text.as_bytes()), &manager).unwrap();
+        assert_eq!(decompressed, Value::SimpleString(text));
+
+        // Scenario 2: Binary content becomes a BulkString, as a SimpleString can't hold it
+        // and converting it lossily would corrupt the stored bytes
+        let binary = vec![0xff, 0xfe, 0x00, 0x80, 0xc3];
+        let decompressed = decompress_single_value_response(encode(&binary), &manager).unwrap();
+        assert_eq!(decompressed, Value::BulkString(binary));
+
+        // Scenario 3: Each value is decompressed exactly once
+        assert_eq!(manager.stats().values_decompressed, 2);
+    }
+
+    #[test]
+    fn test_compress_hash_fields() {
+        use redis::Value;
+
+        let field = "structured.field.name.".repeat(5).into_bytes();
+        let value = "hash value content ".repeat(10).into_bytes();
+        let hset_args = || vec![b"hash".to_vec(), field.clone(), value.clone()];
+
+        // Scenario 1: Flag off, only HSET values are compressed
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        assert!(!manager.config().compress_hash_fields);
+        let mut args = hset_args();
+        process_command_args_for_compression(&mut args, RequestType::HSet, Some(&manager)).unwrap();
+        assert_eq!(args[0], b"hash");
+        assert_eq!(args[1], field);
+        assert!(has_magic_header(&args[2]));
+
+        let response = Value::Array(vec![
+            Value::BulkString(args[1].clone()),
+            Value::BulkString(args[2].clone()),
+        ]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::HGetAll, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(vec![
+                Value::BulkString(field.clone()),
+                Value::BulkString(value.clone()),
+            ])
+        );
+
+        // HKEYS replies are left alone while the flag is off
+        let compressed_field = manager.compress_value(&field).into_owned();
+        let keys = Value::Array(vec![Value::BulkString(compressed_field.clone())]);
+        let untouched =
+            process_response_for_decompression(keys.clone(), RequestType::HKeys, Some(&manager))
+                .unwrap();
+        assert_eq!(untouched, keys);
+
+        // Scenario 2: Flag on, field names are compressed and decompressed too
+        let config =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_compress_hash_fields(true);
+        let manager = CompressionManager::new(
+            Box::new(glide_core::compression::zstd_backend::ZstdBackend::new()),
+            config,
+        )
+        .unwrap();
+        let mut args = hset_args();
+        process_command_args_for_compression(&mut args, RequestType::HSet, Some(&manager)).unwrap();
+        assert_eq!(args[0], b"hash");
+        assert!(has_magic_header(&args[1]));
+        assert!(has_magic_header(&args[2]));
+
+        let response = Value::Map(vec![(
+            Value::BulkString(args[1].clone()),
+            Value::BulkString(args[2].clone()),
+        )]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::HGetAll, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Map(vec![(
+                Value::BulkString(field.clone()),
+                Value::BulkString(value),
+            )])
+        );
+
+        let keys = Value::Array(vec![Value::BulkString(compressed_field)]);
+        let decompressed =
+            process_response_for_decompression(keys, RequestType::HKeys, Some(&manager)).unwrap();
+        assert_eq!(decompressed, Value::Array(vec![Value::BulkString(field)]));
+        assert!(matches!(
+            RequestType::HKeys.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        ));
+    }
+
+    #[test]
+    fn test_decompress_value_tree_containers() {
+        use redis::{PushKind, Value};
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original = "nested tree value ".repeat(10).into_bytes();
+        let compressed = Value::BulkString(manager.compress_value(&original).into_owned());
+        let plain = Value::BulkString(original);
+        let options = TreeDecompressOptions::default();
+
+        // Scenario 1: Nested arrays
+        let tree = Value::Array(vec![
+            compressed.clone(),
+            Value::Array(vec![Value::Int(1), compressed.clone()]),
+        ]);
+        assert_eq!(
+            decompress_value_tree(tree, &manager, options).unwrap(),
+            Value::Array(vec![
+                plain.clone(),
+                Value::Array(vec![Value::Int(1), plain.clone()]),
+            ])
+        );
+
+        // Scenario 2: Map values always, keys only when enabled
+        let map = Value::Map(vec![(
+            compressed.clone(),
+            Value::Array(vec![compressed.clone()]),
+        )]);
+        assert_eq!(
+            decompress_value_tree(map.clone(), &manager, options).unwrap(),
+            Value::Map(vec![(
+                compressed.clone(),
+                Value::Array(vec![plain.clone()])
+            )])
+        );
+        let with_keys = TreeDecompressOptions {
+            include_map_keys: true,
+            ..options
+        };
+        assert_eq!(
+            decompress_value_tree(map, &manager, with_keys).unwrap(),
+            Value::Map(vec![(plain.clone(), Value::Array(vec![plain.clone()]))])
+        );
+
+        // Scenario 3: Set members unless disabled
+        let set = Value::Set(vec![compressed.clone()]);
+        assert_eq!(
+            decompress_value_tree(set.clone(), &manager, options).unwrap(),
+            Value::Set(vec![plain.clone()])
+        );
+        let without_sets = TreeDecompressOptions {
+            include_set_members: false,
+            ..options
+        };
+        assert_eq!(
+            decompress_value_tree(set.clone(), &manager, without_sets).unwrap(),
+            set
+        );
+
+        // Scenario 4: Push messages
+        let push = Value::Push {
+            kind: PushKind::Message,
+            data: vec![
+                Value::BulkString(b"channel".to_vec()),
+                Value::Map(vec![(Value::Int(0), compressed)]),
+            ],
+        };
+        assert_eq!(
+            decompress_value_tree(push, &manager, options).unwrap(),
+            Value::Push {
+                kind: PushKind::Message,
+                data: vec![
+                    Value::BulkString(b"channel".to_vec()),
+                    Value::Map(vec![(Value::Int(0), plain)]),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_skip_numeric_values() {
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_min_compression_size(MIN_COMPRESSED_SIZE);
+
+        // Scenario 1: Numeric values are compressed while the flag is off
+        assert!(!config.skip_numeric_values);
+        assert!(config.should_compress_value(b"12345678"));
+
+        // Scenario 2: Integers and floats are skipped when the flag is on
+        let config = config.with_skip_numeric_values(true);
+        assert!(!config.should_compress_value(b"1234512345"));
+        assert!(!config.should_compress_value(b"-9876543210"));
+        assert!(!config.should_compress_value(b"3.14159265"));
+
+        // Scenario 3: Other values are still compressed
+        let text = "hello world ".repeat(10);
+        assert!(config.should_compress_value(text.as_bytes()));
+
+        let manager = CompressionManager::new(
+            Box::new(glide_core::compression::zstd_backend::ZstdBackend::new()),
+            config,
+        )
+        .unwrap();
+        let counter = "1234567890".repeat(10);
+        assert_eq!(
+            manager.compress_value(counter.as_bytes()),
+            counter.as_bytes()
+        );
+        assert!(has_magic_header(&manager.compress_value(text.as_bytes())));
+    }
+
+    #[test]
+    fn test_explain() {
+        // Scenario 1: SET names the compressed argument, backend, level and size range
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let explanation = manager.explain(RequestType::Set);
+        assert_eq!(
+            explanation,
+            "SET: compresses arg[1] with zstd level 3 for values 64..∞ bytes"
+        );
+
+        // Scenario 2: DEL is not processed
+        assert_eq!(manager.explain(RequestType::Del), "DEL: no compression");
+
+        // Scenario 3: Backend and bounds follow the configuration
+        let manager = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Lz4)
+            .min_size(128)
+            .max_size(4096)
+            .build()
+            .unwrap();
+        let explanation = manager.explain(RequestType::LPush);
+        assert!(explanation.contains("lz4"));
+        assert!(explanation.contains("arg[1..]"));
+        assert!(explanation.contains("128..4096"));
+        assert!(manager.explain(RequestType::Get).contains("decompresses"));
+    }
+
+    #[test]
+    fn test_mset_compressed_before_slot_split() {
+        use redis::cluster_topology::get_slot;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> = (0..6)
+            .map(|i| {
+                (
+                    format!("key-{}", i).into_bytes(),
+                    format!("mset value {} ", i).repeat(10).into_bytes(),
+                )
+            })
+            .collect();
+        let mut args: Vec<Vec<u8>> = pairs
+            .iter()
+            .flat_map(|(key, value)| [key.clone(), value.clone()])
+            .collect();
+
+        // Scenario 1: The full MSET argument list is compressed before splitting
+        assert!(matches!(
+            RequestType::MSet.compression_behavior(),
+            CommandCompressionBehavior::CompressValues
+        ));
+        process_command_args_for_compression(&mut args, RequestType::MSet, Some(&manager)).unwrap();
+        for (index, arg) in args.iter().enumerate() {
+            if index % 2 == 0 {
+                assert_eq!(arg, &pairs[index / 2].0, "keys must stay intact");
+            } else {
+                assert!(has_magic_header(arg));
+            }
+        }
+
+        // Scenario 2: Per-slot fragments keep each compressed value next to its key
+        let mut fragments: std::collections::BTreeMap<u16, Vec<Vec<u8>>> = Default::default();
+        for pair in args.chunks(2) {
+            fragments
+                .entry(get_slot(&pair[0]))
+                .or_default()
+                .extend_from_slice(pair);
+        }
+        for fragment in fragments.values() {
+            for pair in fragment.chunks(2) {
+                let (_, original) = pairs.iter().find(|(key, _)| key == &pair[0]).unwrap();
+                assert_eq!(&manager.decompress_value(&pair[1]).unwrap(), original);
+            }
+        }
+
+        // Scenario 3: MGET reads the values back decompressed, nil for missing keys
+        let mut reply: Vec<redis::Value> = args
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|value| redis::Value::BulkString(value.clone()))
+            .collect();
+        reply.push(redis::Value::Nil);
+        let mut expected: Vec<redis::Value> = pairs
+            .iter()
+            .map(|(_, value)| redis::Value::BulkString(value.clone()))
+            .collect();
+        expected.push(redis::Value::Nil);
+        let response = process_response_for_decompression(
+            redis::Value::Array(reply),
+            RequestType::MGet,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, redis::Value::Array(expected));
+    }
+
+    #[test]
+    fn test_compress_timeout() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::time::{Duration, Instant};
+
+        let data: Vec<u8> = (0..1024 * 1024u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8 % 32)
+            .collect();
+
+        // Scenario 1: The streaming encoder aborts once the deadline has passed
+        let err = ZstdBackend::new()
+            .compress_with_deadline(&data, Some(19), Instant::now())
+            .unwrap_err();
+        assert!(matches!(err, CompressionError::CompressionFailed { .. }));
+        assert!(err.to_string().contains("compression timeout"));
+
+        // Scenario 2: The manager falls back to the raw value
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_compression_level(Some(19))
+            .with_compress_timeout(Some(Duration::from_nanos(1)));
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let (result, compressed) = manager.try_compress_value_reported(&data);
+        assert!(!compressed);
+        assert_eq!(result, data);
+
+        // Scenario 3: A generous timeout produces a regular compressed value
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_compress_timeout(Some(Duration::from_secs(60)));
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let (result, compressed) = manager.try_compress_value_reported(&data);
+        assert!(compressed);
+        assert_eq!(manager.decompress_value(&result).unwrap(), data);
+    }
+
+    #[test]
+    fn test_backend_cache_reuses_instances() {
+        let cache = BackendCache::new();
+
+        // Scenario 1: The same type returns the same instance
+        let first = cache.get_or_init(CompressionBackendType::Zstd);
+        let second = cache.get_or_init(CompressionBackendType::Zstd);
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+        assert_eq!(first.backend_name(), "zstd");
+
+        // Scenario 2: Different types get their own instance
+        let lz4 = cache.get_or_init(CompressionBackendType::Lz4);
+        assert_eq!(lz4.backend_name(), "lz4");
+        assert_ne!(lz4.backend_id(), first.backend_id());
+    }
+
+    #[test]
+    fn test_adaptive_threshold() {
+        let manager = CompressionManagerBuilder::new()
+            .build()
+            .unwrap()
+            .with_adaptive_threshold(1.0, 0.5);
+        let value = |size: usize| "a".repeat(size).into_bytes();
+
+        // Scenario 1: While the average is low, moderately sized values are compressed
+        assert_eq!(manager.adaptive_average(), Some(0.0));
+        assert!(has_magic_header(&manager.compress_value(&value(100))));
+        assert!(has_magic_header(&manager.compress_value(&value(200))));
+        assert_eq!(manager.adaptive_average(), Some(150.0));
+
+        // Scenario 2: Once large values raise the average, small values are skipped
+        for _ in 0..5 {
+            assert!(has_magic_header(&manager.compress_value(&value(4000))));
+        }
+        assert!(manager.adaptive_average().unwrap() > 3000.0);
+        let small = value(200);
+        assert_eq!(manager.compress_value(&small), small.as_slice());
+
+        // Scenario 3: The static minimum size still applies
+        let tiny = value(10);
+        assert_eq!(manager.compress_value(&tiny), tiny.as_slice());
+
+        // Without an adaptive threshold no average is tracked
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        assert_eq!(manager.adaptive_average(), None);
+        assert!(has_magic_header(&manager.compress_value(&small)));
+    }
+
+    #[test]
+    fn test_getdel_response_decompression() {
+        use redis::Value;
+        use std::collections::HashMap;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original = "value removed by getdel ".repeat(10).into_bytes();
+        let mut server: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+
+        // SET key value stores the compressed value
+        let mut args = vec![b"key".to_vec(), original.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        server.insert(args[0].clone(), args[1].clone());
+
+        // Scenario 1: GETDEL replies with the decompressed value and removes the key
+        assert!(matches!(
+            RequestType::GetDel.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        ));
+        let reply = Value::BulkString(server.remove(b"key".as_slice()).unwrap());
+        let decompressed =
+            process_response_for_decompression(reply, RequestType::GetDel, Some(&manager)).unwrap();
+        assert_eq!(decompressed, Value::BulkString(original));
+        assert!(!server.contains_key(b"key".as_slice()));
+
+        // Scenario 2: GETDEL on the missing key replies with nil
+        let decompressed =
+            process_response_for_decompression(Value::Nil, RequestType::GetDel, Some(&manager))
+                .unwrap();
+        assert_eq!(decompressed, Value::Nil);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_pure_rust_decoder_reads_native_values() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original = "value written by a native client ".repeat(50).into_bytes();
+
+        // Scenario 1: A blob produced by the native encoder at a high level
+        let mut native = create_header(CompressionBackendType::Zstd.backend_id()).to_vec();
+        native.extend_from_slice(&zstd::encode_all(original.as_slice(), 19).unwrap());
+        assert_eq!(ZstdBackend::new().decompress(&native).unwrap(), original);
+
+        // Scenario 2: Values framed by the manager, including the optional header fields
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_integrity_mode(IntegrityMode::Crc32);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let compressed = manager.compress_value(&original).into_owned();
+        assert_eq!(manager.decompress_value(&compressed).unwrap(), original);
+
+        // Scenario 3: Corrupted payloads are reported as decompression failures
+        let mut corrupted = native.clone();
+        corrupted.truncate(HEADER_SIZE + 4);
+        assert!(ZstdBackend::new().decompress(&corrupted).is_err());
+
+        // Scenario 4: No zstd library is linked, the self-test runs through the pure-Rust codec
+        let backend = ZstdBackend::new().with_dictionary(7, b"shared dictionary".repeat(8));
+        assert_eq!(backend.version(), 0);
+        assert!(backend.health_check().is_ok());
+
+        // Scenario 5: Dictionaries are reported as unsupported rather than misencoded
+        let err = backend
+            .compress_with_dictionary(&original, None, 7, 0)
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("not supported by the pure-Rust codec")
+        );
+    }
+
+    #[test]
+    fn test_compress_into_reuses_buffer() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let mut buffer = Vec::with_capacity(4096);
+
+        // Scenario 1: Several values compressed into the same buffer
+        for (i, pattern) in ["alpha ", "beta beta ", "gamma gamma gamma "]
+            .iter()
+            .enumerate()
+        {
+            let value = pattern.repeat(20 + i * 10).into_bytes();
+            assert!(manager.compress_into(&value, &mut buffer).unwrap());
+            assert_eq!(buffer, manager.compress_value(&value).into_owned());
+            assert_eq!(manager.decompress_value(&buffer).unwrap(), value);
+        }
+        assert!(buffer.capacity() >= 4096);
+
+        // Scenario 2: Values that aren't compressed are copied as-is, replacing the previous output
+        let small = b"short".to_vec();
+        assert!(!manager.compress_into(&small, &mut buffer).unwrap());
+        assert_eq!(buffer, small);
+    }
+
+    #[test]
+    fn test_json_mget_response_decompression() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let first = format!("[{{\"name\":\"{}\"}}]", "first document ".repeat(10)).into_bytes();
+        let second = format!("[{{\"name\":\"{}\"}}]", "second document ".repeat(10)).into_bytes();
+
+        assert!(matches!(
+            RequestType::JsonMGet.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        ));
+
+        // Compressed JSON strings, a missing key and an uncompressed document
+        let response = Value::Array(vec![
+            Value::BulkString(manager.compress_value(&first).into_owned()),
+            Value::Nil,
+            Value::BulkString(manager.compress_value(&second).into_owned()),
+            Value::BulkString(b"[1]".to_vec()),
+        ]);
+        let decompressed =
+            process_response_for_decompression(response, RequestType::JsonMGet, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(vec![
+                Value::BulkString(first),
+                Value::Nil,
+                Value::BulkString(second),
+                Value::BulkString(b"[1]".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_buildable() {
+        // Scenario 1: Compiled backends are buildable, the others fail with UnsupportedBackend
+        // but still pass the plain validation
+        let compiled = compiled_backends();
+        for backend in CompressionBackendType::all() {
+            let config = CompressionConfig::new(backend);
+            assert!(config.validate().is_ok());
+            if compiled.contains(&backend) {
+                assert!(config.validate_buildable().is_ok());
+            } else {
+                let err = config.validate_buildable().unwrap_err();
+                assert!(matches!(err, CompressionError::UnsupportedBackend { .. }));
+                assert!(err.to_string().contains("support not compiled in"), "{err}");
+                assert!(CompressionManager::new(backend.make_backend(), config).is_err());
+            }
+        }
+
+        // Scenario 2: The regular validation still runs after the availability check
+        let config =
+            CompressionConfig::new(CompressionBackendType::Lz4).with_min_compression_size(1);
+        let err = config.validate_buildable().unwrap_err();
+        assert!(matches!(err, CompressionError::InvalidConfiguration { .. }));
+        assert_eq!(err, config.validate().unwrap_err());
+    }
+
+    #[test]
+    fn test_header_fields_are_little_endian() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original = "byte order test ".repeat(20).into_bytes();
+        let length = original.len() as u32;
+        let crc = crc32fast::hash(&original);
+
+        // Scenario 1: A hand-constructed header with little-endian fields
+        let mut frame = vec![
+            0x00,
+            0x01,
+            0x02,
+            FLAGS_VERSION,
+            CompressionBackendType::Zstd.backend_id(),
+            FLAG_ORIGINAL_LENGTH | FLAG_CRC32,
+        ];
+        frame.extend_from_slice(&[
+            length as u8,
+            (length >> 8) as u8,
+            (length >> 16) as u8,
+            (length >> 24) as u8,
+        ]);
+        frame.extend_from_slice(&[
+            crc as u8,
+            (crc >> 8) as u8,
+            (crc >> 16) as u8,
+            (crc >> 24) as u8,
+        ]);
+
+        let header = FrameHeader::parse(&frame).unwrap();
+        assert_eq!(header.original_length, Some(length));
+        assert_eq!(header.crc32, Some(crc));
+        assert_eq!(header.to_bytes(), frame);
+
+        // Scenario 2: The hand-built frame decompresses and passes verification
+        let compressed = ZstdBackend::new().compress(&original, None).unwrap();
+        frame.extend_from_slice(&compressed[HEADER_SIZE..]);
+        assert_eq!(ZstdBackend::new().decompress(&frame).unwrap(), original);
+
+        // Scenario 3: The same fields in big-endian order are rejected
+        let mut swapped = frame.clone();
+        swapped[6..10].reverse();
+        assert!(ZstdBackend::new().decompress(&swapped).is_err());
+    }
+
+    /// Backend panicking on any codec use, to prove a code path never reaches the backend
+    #[derive(Debug)]
+    struct PanickingBackend;
+
+    impl CompressionBackend for PanickingBackend {
+        fn compress(&self, _data: &[u8], _level: Option<i32>) -> CompressionResult<Vec<u8>> {
+            panic!("compress must not be called");
+        }
+        fn decompress(&self, _data: &[u8]) -> CompressionResult<Vec<u8>> {
+            panic!("decompress must not be called");
+        }
+        fn is_compressed(&self, _data: &[u8]) -> bool {
+            panic!("is_compressed must not be called");
+        }
+        fn backend_name(&self) -> &'static str {
+            "panicking"
+        }
+        fn default_level(&self) -> Option<i32> {
+            None
+        }
+        fn backend_id(&self) -> u8 {
+            CompressionBackendType::Zstd.backend_id()
+        }
+        fn supports_level(&self, _level: i32) -> bool {
+            true
+        }
+        fn validate_compression_level(&self, _level: Option<i32>) -> CompressionResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_short_inputs_skip_backend() {
+        let manager = CompressionManager::new(
+            Box::new(PanickingBackend),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+
+        // Scenario 1: A 3-byte reply, even one matching the magic prefix
+        let short = [0x00, 0x01, 0x02];
+        assert_eq!(manager.decompress_value(&short).unwrap(), short);
+        assert_eq!(manager.try_decompress_value(b"abc"), b"abc");
+
+        // Scenario 2: Every length below the minimum compressed size
+        let header = create_header(CompressionBackendType::Zstd.backend_id());
+        for len in 0..MIN_COMPRESSED_SIZE {
+            assert!(matches!(
+                manager.decompress_value_cow(&header[..len.min(HEADER_SIZE)]),
+                Ok(std::borrow::Cow::Borrowed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_compress_eval_argv_indices() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let large_key = "large key name ".repeat(10).into_bytes();
+        let large_arg = "large script argument ".repeat(10).into_bytes();
+        let eval_args = || {
+            vec![
+                b"return redis.call('SET', KEYS[1], ARGV[2])".to_vec(),
+                b"1".to_vec(),
+                large_key.clone(),
+                b"small".to_vec(),
+                large_arg.clone(),
+            ]
+        };
+
+        // Scenario 1: Nothing is compressed by default
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let mut args = eval_args();
+        process_command_args_for_compression(&mut args, RequestType::Eval, Some(&manager)).unwrap();
+        assert_eq!(args, eval_args());
+
+        // Scenario 2: Only the configured ARGV position is compressed, KEYS stay untouched
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_compress_eval_argv_indices([1]);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        for request_type in [RequestType::Eval, RequestType::EvalSha, RequestType::FCall] {
+            let mut args = eval_args();
+            process_command_args_for_compression(&mut args, request_type, Some(&manager)).unwrap();
+            assert_eq!(args[..4], eval_args()[..4]);
+            assert_eq!(manager.decompress_value(&args[4]).unwrap(), large_arg);
+            assert!(has_magic_header(&args[4]));
+        }
+
+        // Scenario 3: Positions past the end and malformed key counts are ignored
+        let mut args = eval_args();
+        args.truncate(4);
+        process_command_args_for_compression(&mut args, RequestType::Eval, Some(&manager)).unwrap();
+        assert_eq!(args, eval_args()[..4]);
+        let mut args = eval_args();
+        args[1] = b"not a number".to_vec();
+        let expected = args.clone();
+        process_command_args_for_compression(&mut args, RequestType::Eval, Some(&manager)).unwrap();
+        assert_eq!(args, expected);
+
+        // Scenario 4: A key count overflowing the position computation leaves the args alone
+        for num_keys in [usize::MAX, usize::MAX - 2, usize::MAX / 2] {
+            let mut args = eval_args();
+            args[1] = num_keys.to_string().into_bytes();
+            let expected = args.clone();
+            process_command_args_for_compression(&mut args, RequestType::Eval, Some(&manager))
+                .unwrap();
+            assert_eq!(args, expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid_configuration_carries_config() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        // Scenario 1: Rejected by config validation
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_min_compression_size(2048)
+            .with_max_compression_size(Some(1024));
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.config(), Some(&config));
+
+        // Scenario 2: Rejected by the manager, the message is unchanged
+        let err =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap_err();
+        assert_eq!(err.config(), Some(&config));
+        assert!(
+            err.to_string()
+                .starts_with("Invalid compression configuration for 'zstd': ")
+        );
+        assert!(!err.to_string().contains("CompressionConfig"));
+
+        // Scenario 3: Backend level checks during construction attach the config too
+        let config =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_compression_level(Some(999));
+        let err =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap_err();
+        assert_eq!(err.config().and_then(|c| c.compression_level), Some(999));
+
+        // Other errors carry no configuration
+        assert_eq!(
+            CompressionError::unsupported_backend("brotli").config(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_prepare_append() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let first = "log line one\n".repeat(10).into_bytes();
+        let second = "log line two\n".repeat(10).into_bytes();
+        let mut expected = first.clone();
+        expected.extend_from_slice(&second);
+
+        // Scenario 1: Appending to a compressed value
+        let existing = manager.compress_value(&first).into_owned();
+        let updated = manager.prepare_append(&existing, &second).unwrap();
+        assert!(has_magic_header(&updated));
+        assert_eq!(manager.decompress_value(&updated).unwrap(), expected);
+
+        // Scenario 2: Appending to a raw value compresses the result once large enough
+        let updated = manager.prepare_append(b"", &first).unwrap();
+        assert_eq!(manager.decompress_value(&updated).unwrap(), first);
+        let updated = manager.prepare_append(b"ab", b"cd").unwrap();
+        assert_eq!(updated, b"abcd");
+
+        // Scenario 3: A corrupted existing value is reported instead of being overwritten
+        let mut corrupted = existing.clone();
+        corrupted.truncate(HEADER_SIZE + 3);
+        assert!(manager.prepare_append(&corrupted, &second).is_err());
+    }
+
+    #[test]
+    fn test_header_size_for_flags() {
+        // Scenario 1: Each flag adds the size of its field to the flags byte
+        assert_eq!(header_size_for(0), HEADER_SIZE + 1);
+        assert_eq!(header_size_for(FLAG_ORIGINAL_LENGTH), HEADER_SIZE + 5);
+        assert_eq!(header_size_for(FLAG_CRC32), HEADER_SIZE + 5);
+        assert_eq!(header_size_for(FLAG_LEVEL), HEADER_SIZE + 2);
+        assert_eq!(header_size_for(FLAG_FRAME_LENGTH), HEADER_SIZE + 5);
+        assert_eq!(header_size_for(FLAG_DICTIONARY), HEADER_SIZE + 6);
+
+        // Scenario 2: Every combination matches the encoded header and stays within the maximum
+        let all = [
+            FLAG_ORIGINAL_LENGTH,
+            FLAG_CRC32,
+            FLAG_LEVEL,
+            FLAG_FRAME_LENGTH,
+            FLAG_DICTIONARY,
+        ];
+        for combination in 0..(1u8 << all.len()) {
+            let flags = all
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| combination & (1 << bit) != 0)
+                .fold(0, |flags, (_, flag)| flags | flag);
+            let header = FrameHeader {
+                version: FLAGS_VERSION,
+                backend_id: CompressionBackendType::Zstd.backend_id(),
+                flags,
+                original_length: (flags & FLAG_ORIGINAL_LENGTH != 0).then_some(1),
+                crc32: (flags & FLAG_CRC32 != 0).then_some(2),
+                level: (flags & FLAG_LEVEL != 0).then_some(3),
+                frame_length: (flags & FLAG_FRAME_LENGTH != 0).then_some(4),
+                dictionary_id: (flags & FLAG_DICTIONARY != 0).then_some(5),
+                dictionary_epoch: if flags & FLAG_DICTIONARY != 0 { 6 } else { 0 },
+            };
+            let bytes = header.to_bytes();
+            assert_eq!(bytes.len(), header_size_for(flags));
+            assert_eq!(header.encoded_len(), header_size_for(flags));
+            assert!(header_size_for(flags) <= MAX_HEADER_SIZE);
+            assert_eq!(FrameHeader::parse(&bytes), Some(header));
+        }
+        assert_eq!(
+            header_size_for(
+                FLAG_ORIGINAL_LENGTH
+                    | FLAG_CRC32
+                    | FLAG_LEVEL
+                    | FLAG_FRAME_LENGTH
+                    | FLAG_DICTIONARY
+            ),
+            MAX_HEADER_SIZE
+        );
+    }
+
+    #[test]
+    fn test_hvals_response_decompression() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let values: Vec<Vec<u8>> = (0..3)
+            .map(|i| format!("hash value {} ", i).repeat(10).into_bytes())
+            .collect();
+
+        assert!(matches!(
+            RequestType::HVals.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        ));
+
+        // HSET values are compressed, HVALS returns them in an array
+        let mut args = vec![b"hash".to_vec()];
+        for (i, value) in values.iter().enumerate() {
+            args.push(format!("field{}", i).into_bytes());
+            args.push(value.clone());
+        }
+        process_command_args_for_compression(&mut args, RequestType::HSet, Some(&manager)).unwrap();
+        let response = Value::Array(
+            args.iter()
+                .skip(2)
+                .step_by(2)
+                .map(|value| Value::BulkString(value.clone()))
+                .collect(),
+        );
+        let decompressed =
+            process_response_for_decompression(response, RequestType::HVals, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            decompressed,
+            Value::Array(values.into_iter().map(Value::BulkString).collect())
+        );
+    }
+
+    #[test]
+    fn test_estimate_savings() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+
+        // Scenario 1: Mixed compressible, incompressible and small samples
+        let compressible = "repetitive sample data ".repeat(50).into_bytes();
+        let incompressible: Vec<u8> = (0..512u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 7) as u8)
+            .collect();
+        let small = b"tiny".to_vec();
+        let samples = vec![compressible.clone(), incompressible.clone(), small.clone()];
+
+        let report = manager.estimate_savings(&samples);
+        let compressed_size = manager.compress_value(&compressible).len();
+        assert_eq!(
+            report.total_in,
+            compressible.len() + incompressible.len() + small.len()
+        );
+        assert_eq!(report.compressed_count, 1);
+        assert_eq!(report.skipped_count, 2);
+        assert_eq!(
+            report.total_out,
+            compressed_size + incompressible.len() + small.len()
+        );
+        assert!(report.total_out < report.total_in);
+        let expected_ratio = compressed_size as f64 / compressible.len() as f64;
+        assert!((report.avg_ratio - expected_ratio).abs() < 1e-9);
+
+        // Scenario 2: Estimating doesn't touch the counters
+        let stats = manager.reset_stats();
+        assert_eq!(stats.values_compressed, 1);
+        manager.estimate_savings(&samples);
+        assert_eq!(manager.stats(), CompressionStats::default());
+
+        // Scenario 3: Nothing to compress
+        let report = manager.estimate_savings(&[small]);
+        assert_eq!(report.compressed_count, 0);
+        assert_eq!(report.total_in, report.total_out);
+        assert_eq!(report.avg_ratio, 1.0);
+    }
+
+    #[test]
+    fn test_strict_decompress() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let original = "value wrapped twice ".repeat(20).into_bytes();
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_integrity_mode(IntegrityMode::LengthOnly);
+        let lenient =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        let strict = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            config.with_strict_decompress(true),
+        )
+        .unwrap();
+
+        // Scenario 1: Values declaring their length decompress normally
+        let inner = lenient.compress_value(&original).into_owned();
+        assert_eq!(strict.decompress_value(&inner).unwrap(), original);
+
+        // Scenario 2: A crafted double header, a compressed value wrapped in a second frame
+        let declared = FrameHeader::with_integrity(
+            CompressionBackendType::Zstd.backend_id(),
+            IntegrityMode::LengthOnly,
+            &inner,
+        );
+        let mut double = declared.to_bytes();
+        double
+            .extend_from_slice(&ZstdBackend::new().compress(&inner, None).unwrap()[HEADER_SIZE..]);
+        assert_eq!(lenient.decompress_value(&double).unwrap(), inner);
+        let err = strict.decompress_value(&double).unwrap_err();
+        assert!(err.to_string().contains("still compressed"));
+
+        // Scenario 3: Values without a declared length are rejected
+        let legacy = ZstdBackend::new().compress(&original, None).unwrap();
+        assert_eq!(lenient.decompress_value(&legacy).unwrap(), original);
+        let err = strict.decompress_value(&legacy).unwrap_err();
+        assert!(err.to_string().contains("declared original length"));
+        assert_eq!(strict.try_decompress_value(&legacy), legacy);
+    }
+
+    #[test]
+    fn test_decompress_value_with_meta() {
+        let manager = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Lz4)
+            .build()
+            .unwrap();
+        let original = "value with metadata ".repeat(20).into_bytes();
+
+        // Scenario 1: Compressed input
+        let compressed = manager.compress_value(&original).into_owned();
+        let (value, meta) = manager.decompress_value_with_meta(&compressed).unwrap();
+        assert_eq!(value, original);
+        assert_eq!(
+            meta,
+            DecompressMeta {
+                was_compressed: true,
+                compressed_size: compressed.len(),
+                decompressed_size: original.len(),
+                backend: Some(CompressionBackendType::Lz4),
+            }
+        );
+
+        // Scenario 2: Raw input
+        let (value, meta) = manager.decompress_value_with_meta(b"raw value").unwrap();
+        assert_eq!(value, b"raw value");
+        assert_eq!(
+            meta,
+            DecompressMeta {
+                was_compressed: false,
+                compressed_size: 9,
+                decompressed_size: 9,
+                backend: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_backend_validate_without_decompressing() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let backend = ZstdBackend::new();
+        let original = "value scanned for corruption ".repeat(40).into_bytes();
+
+        // Scenario 1: Good blobs pass, with and without integrity fields
+        let legacy = backend.compress(&original, None).unwrap();
+        assert!(backend.validate(&legacy).is_ok());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_integrity_mode(IntegrityMode::Crc32);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let checked = manager.compress_value(&original).into_owned();
+        assert!(backend.validate(&checked).is_ok());
+
+        // Scenario 2: A truncated blob with a valid header fails
+        let truncated = &legacy[..legacy.len() / 2];
+        assert!(FrameHeader::parse(truncated).is_some());
+        assert!(backend.validate(truncated).is_err());
+
+        // Scenario 3: A payload decoding to other contents fails the header checks
+        let other = backend.compress(b"other contents entirely", None).unwrap();
+        let mut mismatched = FrameHeader::parse(&checked).unwrap().to_bytes();
+        mismatched.extend_from_slice(&other[HEADER_SIZE..]);
+        assert!(backend.validate(&mismatched).is_err());
+
+        // Scenario 4: Raw data fails, and the default implementation only checks the header
+        assert!(backend.validate(b"plain value").is_err());
+        let lz4 = Lz4Backend::new();
+        assert!(
+            lz4.validate(&lz4.compress(&original, None).unwrap())
+                .is_ok()
+        );
+        assert!(lz4.validate(&legacy).is_err());
+    }
+
+    #[test]
+    fn test_compression_level_effort() {
+        // Scenario 1: Effort values mapped onto each backend's scale
+        let zstd = CompressionBackendType::Zstd;
+        let lz4 = CompressionBackendType::Lz4;
+        let cases = [
+            (0.0, 1, 0),
+            (0.25, 6, 3),
+            (0.5, 11, 6),
+            (0.75, 17, 9),
+            (1.0, 22, 12),
+        ];
+        for (effort, zstd_level, lz4_level) in cases {
+            let level = CompressionLevel::new(effort);
+            assert_eq!(level.as_backend_level(zstd), Some(zstd_level), "{}", effort);
+            assert_eq!(level.as_backend_level(lz4), Some(lz4_level), "{}", effort);
+        }
+
+        // Scenario 2: Out of range efforts are clamped and levels are ordered
+        assert_eq!(CompressionLevel::new(-1.0), CompressionLevel::FASTEST);
+        assert_eq!(CompressionLevel::new(2.0), CompressionLevel::BEST);
+        assert_eq!(CompressionLevel::new(f32::NAN), CompressionLevel::FASTEST);
+        assert!(CompressionLevel::FASTEST < CompressionLevel::BALANCED);
+        assert!(CompressionLevel::BALANCED < CompressionLevel::BEST);
+
+        // Scenario 3: Every mapped level is accepted by the backend
+        for backend in CompressionBackendType::all() {
+            for step in 0..=10 {
+                let config = CompressionConfig::new(backend).with_effort(step as f32 / 10.0);
+                let level = config.compression_level.unwrap();
+                assert!(backend.make_backend().supports_level(level));
+                assert!(CompressionManager::new(backend.make_backend(), config).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_disabled_skip_counter() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let value = "value sent while disabled ".repeat(10).into_bytes();
+
+        // Scenario 1: Every attempt on a disabled manager counts as a disabled skip
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), CompressionConfig::disabled())
+                .unwrap();
+        for attempt in 1..=3 {
+            let (result, compressed) = manager.try_compress_value_reported(&value);
+            assert!(!compressed);
+            assert_eq!(result, value);
+            let stats = manager.stats();
+            assert_eq!(stats.values_skipped_disabled, attempt);
+            assert_eq!(stats.values_skipped, attempt);
+        }
+
+        // Scenario 2: Values below the threshold are skipped without counting as disabled
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        manager.compress_value(b"short");
+        manager.compress_value(&value);
+        let stats = manager.stats();
+        assert_eq!(stats.values_skipped, 1);
+        assert_eq!(stats.values_skipped_disabled, 0);
+        assert_eq!(stats.values_compressed, 1);
+
+        // Scenario 3: Switching the configuration off at runtime
+        manager.swap_config(CompressionConfig::disabled()).unwrap();
+        manager.compress_value(&value);
+        assert_eq!(manager.reset_stats().values_skipped_disabled, 1);
+        assert_eq!(manager.stats().values_skipped_disabled, 0);
+    }
+
+    #[test]
+    fn test_force_compress() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let forced = CompressionConfig::new(CompressionBackendType::Zstd).with_force_compress(true);
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), forced.clone()).unwrap();
+
+        // Scenario 1: A 1-byte value is compressed even though it grows
+        let compressed = manager.compress_value(b"x");
+        assert!(is_glide_compressed(&compressed));
+        assert!(compressed.len() > 1);
+        assert_eq!(manager.decompress_value(&compressed).unwrap(), b"x");
+        assert_eq!(manager.stats().values_compressed, 1);
+
+        // Scenario 2: Empty values are still never framed
+        assert_eq!(manager.compress_value(b"").as_ref(), b"");
+
+        // Scenario 3: max_compression_size and enabled still apply
+        let bounded = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            forced.clone().with_max_compression_size(Some(4)),
+        )
+        .unwrap();
+        assert_eq!(bounded.compress_value(b"too long").as_ref(), b"too long");
+        assert!(is_glide_compressed(&bounded.compress_value(b"ok")));
+
+        let mut disabled = forced;
+        disabled.enabled = false;
+        let disabled = CompressionManager::new(Box::new(ZstdBackend::new()), disabled).unwrap();
+        assert_eq!(disabled.compress_value(b"x").as_ref(), b"x");
+
+        // Scenario 4: Without the option the same value is left alone
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        assert_eq!(manager.compress_value(b"x").as_ref(), b"x");
+    }
+
+    #[test]
+    fn test_forced_mode_never_double_frames() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::borrow::Cow;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_force_compress(true)
+            .with_integrity_mode(IntegrityMode::Crc32);
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+        let original = "framed by another client ".repeat(8).into_bytes();
+        let framed = manager.compress_value(&original).into_owned();
+        assert!(is_glide_compressed(&framed));
+
+        // Scenario 1: Already framed values are returned as-is by every compression path
+        assert!(matches!(manager.compress_value(&framed), Cow::Borrowed(_)));
+        assert_eq!(
+            manager.try_compress_value_reported(&framed),
+            (framed.clone(), false)
+        );
+        assert_eq!(
+            manager.compress_value_with_level(&framed, Some(1)).unwrap(),
+            framed
+        );
+        let mut out = Vec::new();
+        assert!(!manager.compress_into(&framed, &mut out).unwrap());
+        assert_eq!(out, framed);
+
+        // Scenario 2: A single decompression restores the original value
+        assert_eq!(manager.decompress_value(&framed).unwrap(), original);
+
+        // Scenario 3: Estimates don't count framed values as compressible
+        let report = manager.estimate_savings(std::slice::from_ref(&framed));
+        assert_eq!(report.compressed_count, 0);
+        assert_eq!(report.skipped_count, 1);
+    }
+
+    #[test]
+    fn test_compressing_and_decompressing_commands() {
+        use glide_core::compression::{compressing_commands, decompressing_commands};
+
+        // Scenario 1: Every listed command classifies accordingly
+        assert!(!compressing_commands().is_empty());
+        for request_type in compressing_commands() {
+            assert_eq!(
+                request_type.compression_behavior(),
+                CommandCompressionBehavior::CompressValues,
+                "{request_type:?}"
+            );
+        }
+        for request_type in decompressing_commands() {
+            assert_eq!(
+                request_type.compression_behavior(),
+                CommandCompressionBehavior::DecompressValues,
+                "{request_type:?}"
+            );
+        }
+
+        // Scenario 2: The lists don't overlap and leave other commands alone
+        assert!(
+            compressing_commands()
+                .iter()
+                .all(|request_type| !decompressing_commands().contains(request_type))
+        );
+        assert!(compressing_commands().contains(&RequestType::Set));
+        assert!(decompressing_commands().contains(&RequestType::Get));
+        assert_eq!(
+            RequestType::BitCount.compression_behavior(),
+            CommandCompressionBehavior::NoCompression
+        );
+    }
+
+    #[test]
+    fn test_command_specs_are_consistent() {
+        use glide_core::compression::{COMMAND_SPECS, ResponseShape, ValuePositions, command_spec};
+
+        for spec in COMMAND_SPECS {
+            // Scenario 1: The table drives classification, one entry per command
+            assert_eq!(spec.request_type.compression_behavior(), spec.behavior);
+            assert_eq!(command_spec(spec.request_type), Some(spec));
+
+            match spec.behavior {
+                // Scenario 2: Compressing commands declare at least one value position
+                CommandCompressionBehavior::CompressValues => {
+                    assert_ne!(spec.value_positions, ValuePositions::None, "{spec:?}");
+                    if let ValuePositions::At(index) | ValuePositions::From(index) =
+                        spec.value_positions
+                    {
+                        // The key at position 0 is never compressed
+                        assert!(index > 0, "{spec:?}");
+                    }
+                }
+                // Scenario 3: Decompressing commands declare how their reply is laid out
+                CommandCompressionBehavior::DecompressValues => {
+                    assert_ne!(spec.response_shape, ResponseShape::None, "{spec:?}");
+                    assert_eq!(spec.value_positions, ValuePositions::None, "{spec:?}");
+                }
+                CommandCompressionBehavior::NoCompression => {
+                    panic!("{spec:?} should not be listed")
+                }
+            }
+        }
+
+        // Scenario 4: Unlisted commands have no spec
+        assert!(command_spec(RequestType::BitCount).is_none());
+    }
+
+    #[test]
+    fn test_decompress_value_or_raw() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let failures = Arc::new(AtomicUsize::new(0));
+        let counter = failures.clone();
+        let config =
+            CompressionConfig::new(CompressionBackendType::Zstd).with_on_fallback(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            });
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap();
+
+        // Scenario 1: Undecodable data in our format loses its header instead of leaking it
+        let mut undecodable = create_header(CompressionBackendType::Zstd.backend_id()).to_vec();
+        undecodable.extend_from_slice(b"garbled payload");
+        assert_eq!(manager.try_decompress_value(&undecodable), undecodable);
+        assert_eq!(failures.load(Ordering::Relaxed), 1);
+        let raw = manager.decompress_value_or_raw(&undecodable);
+        assert_eq!(raw, b"garbled payload");
+        assert!(!raw.starts_with(b"GLID"));
+        assert_eq!(failures.load(Ordering::Relaxed), 2);
+
+        // Scenario 2: Valid compressed data is decompressed
+        let original = "readable payload ".repeat(10).into_bytes();
+        let compressed = manager.compress_value(&original).into_owned();
+        assert_eq!(manager.decompress_value_or_raw(&compressed), original);
+
+        // Scenario 3: Data in another format is returned untouched
+        assert_eq!(manager.decompress_value_or_raw(b"plain"), b"plain");
+        assert_eq!(failures.load(Ordering::Relaxed), 2);
+
+        // Scenario 4: decompress_or_strip behaves the same, being the same function
+        assert_eq!(manager.decompress_or_strip(&undecodable), raw);
+        assert_eq!(failures.load(Ordering::Relaxed), 3);
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_zstd_content_size_preallocation() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::time::{Duration, Instant};
+
+        let backend = ZstdBackend::new();
+        let original: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+
+        // Scenario 1: The frame records the content size and the output is allocated once
+        let compressed = backend.compress(&original, None).unwrap();
+        assert_eq!(
+            zstd::zstd_safe::get_frame_content_size(&compressed[HEADER_SIZE..]).unwrap(),
+            Some(original.len() as u64)
+        );
+        let decompressed = backend.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, original);
+        assert_eq!(decompressed.capacity(), original.len());
+
+        // Scenario 2: The streaming encoder used with a deadline records it as well
+        let streamed = backend
+            .compress_with_deadline(&original, None, Instant::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(
+            zstd::zstd_safe::get_frame_content_size(&streamed[HEADER_SIZE..]).unwrap(),
+            Some(original.len() as u64)
+        );
+        assert_eq!(
+            backend.decompress(&streamed).unwrap().capacity(),
+            original.len()
+        );
+    }
+
+    #[test]
+    fn test_control_values_pass_through_walkers() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let original = "array element ".repeat(10).into_bytes();
+        let compressed = manager.compress_value(&original).into_owned();
+        let server_error = || {
+            Value::ServerError(
+                redis::RedisError::from((redis::ErrorKind::ResponseError, "boom")).into(),
+            )
+        };
+
+        // Scenario 1: An array mixing a compressed value with control values
+        let response = Value::Array(vec![
+            Value::BulkString(compressed.clone()),
+            server_error(),
+            Value::Okay,
+            Value::Nil,
+        ]);
+        let result =
+            process_response_for_decompression(response, RequestType::JsonMGet, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::BulkString(original.clone()),
+                server_error(),
+                Value::Okay,
+                Value::Nil,
+            ])
+        );
+
+        // Scenario 2: The tree walker leaves them untouched in maps and sets as well
+        let tree = Value::Map(vec![
+            (Value::BulkString(b"field".to_vec()), server_error()),
+            (
+                Value::BulkString(b"other".to_vec()),
+                Value::Set(vec![Value::BulkString(compressed), Value::Okay]),
+            ),
+        ]);
+        let result =
+            decompress_value_tree(tree, &manager, TreeDecompressOptions::default()).unwrap();
+        assert_eq!(
+            result,
+            Value::Map(vec![
+                (Value::BulkString(b"field".to_vec()), server_error()),
+                (
+                    Value::BulkString(b"other".to_vec()),
+                    Value::Set(vec![Value::BulkString(original), Value::Okay]),
+                ),
+            ])
+        );
+
+        // Scenario 3: A lone error reply is returned as-is
+        assert_eq!(
+            process_response_for_decompression(server_error(), RequestType::Get, Some(&manager))
+                .unwrap(),
+            server_error()
+        );
+    }
+
+    #[test]
+    fn test_new_disabled_manager() {
+        use redis::Value;
+
+        let manager = CompressionManager::new_disabled();
+        let value = "value passing through ".repeat(10).into_bytes();
+
+        // Scenario 1: The manager reports compression as disabled
+        assert!(!manager.is_enabled());
+        assert_eq!(manager.backend_name(), "zstd");
+
+        // Scenario 2: Commands and values pass through unchanged
+        assert_eq!(manager.compress_value(&value).as_ref(), value.as_slice());
+        let mut args = vec![b"key".to_vec(), value.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert_eq!(args, vec![b"key".to_vec(), value.clone()]);
+
+        // Scenario 3: Replies pass through unchanged, even when compressed
+        let compressed = CompressionManagerBuilder::new()
+            .build()
+            .unwrap()
+            .compress_value(&value)
+            .into_owned();
+        let reply = Value::BulkString(compressed.clone());
+        assert_eq!(
+            process_response_for_decompression(reply.clone(), RequestType::Get, Some(&manager))
+                .unwrap(),
+            reply
+        );
+
+        // Scenario 4: Compression can be turned on later
+        manager
+            .swap_config(CompressionConfig::new(CompressionBackendType::Zstd))
+            .unwrap();
+        assert!(manager.is_enabled());
+        assert_ne!(manager.compress_value(&value).as_ref(), value.as_slice());
+    }
+
+    #[test]
+    fn test_spop_response_forms() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let members: Vec<Vec<u8>> = (0..3)
+            .map(|i| format!("set member {i} ").repeat(8).into_bytes())
+            .collect();
+        let compressed: Vec<Vec<u8>> = members
+            .iter()
+            .map(|member| manager.compress_value(member).into_owned())
+            .collect();
+
+        // Scenario 1: SPOP key replies with a single member
+        let result = process_response_for_decompression(
+            Value::BulkString(compressed[0].clone()),
+            RequestType::SPop,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(result, Value::BulkString(members[0].clone()));
+
+        // Scenario 2: SPOP key count replies with an array (RESP2) of members
+        let array = Value::Array(compressed.iter().cloned().map(Value::BulkString).collect());
+        let expected: Vec<Value> = members.iter().cloned().map(Value::BulkString).collect();
+        assert_eq!(
+            process_response_for_decompression(array, RequestType::SPop, Some(&manager)).unwrap(),
+            Value::Array(expected.clone())
+        );
+
+        // Scenario 3: ... or a set (RESP3)
+        let set = Value::Set(compressed.into_iter().map(Value::BulkString).collect());
+        assert_eq!(
+            process_response_for_decompression(set, RequestType::SPop, Some(&manager)).unwrap(),
+            Value::Set(expected)
+        );
+
+        // Scenario 4: Popping from a missing key replies with nil
+        assert_eq!(
+            process_response_for_decompression(Value::Nil, RequestType::SPop, Some(&manager))
+                .unwrap(),
+            Value::Nil
+        );
+        assert_eq!(
+            RequestType::SPop.compression_behavior(),
+            CommandCompressionBehavior::DecompressValues
+        );
+    }
+
+    #[test]
+    fn test_content_classifiers() {
+        use glide_core::compression::{
+            ContentClassifier, MagicByteSniffer, ShannonEntropyClassifier,
+        };
+
+        let text = "plain text compresses well ".repeat(20).into_bytes();
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png.extend_from_slice(&[0u8; 200]);
+
+        // Scenario 1: The sniffer rejects a PNG header and accepts plain text
+        assert!(!MagicByteSniffer.is_worth_compressing(&png));
+        assert!(MagicByteSniffer.is_worth_compressing(&text));
+        assert!(!MagicByteSniffer.is_worth_compressing(&[0x1f, 0x8b, 0x08, 0x00]));
+        assert!(!MagicByteSniffer.is_worth_compressing(b"PK\x03\x04rest"));
+        assert!(!MagicByteSniffer.is_worth_compressing(&[0xff, 0xd8, 0xff, 0xe0]));
+
+        // Scenario 2: The entropy classifier rejects random-looking bytes
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let random: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect();
+        let entropy = ShannonEntropyClassifier::default();
+        assert!(entropy.entropy(&random) > 7.9);
+        assert!(!entropy.is_worth_compressing(&random));
+        assert!(entropy.is_worth_compressing(&text));
+        assert_eq!(entropy.entropy(&[7u8; 100]), 0.0);
+        assert!(ShannonEntropyClassifier::new(8.0).is_worth_compressing(&random));
+
+        // Scenario 3: The manager consults the classifier before compressing
+        let manager = CompressionManagerBuilder::new()
+            .build()
+            .unwrap()
+            .with_classifier(Box::new(MagicByteSniffer));
+        assert!(!manager.should_compress(&png));
+        assert_eq!(manager.compress_value(&png).as_ref(), png.as_slice());
+        let mut args = vec![b"key".to_vec(), png.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        assert_eq!(args[1], png);
+        assert!(manager.should_compress(&text));
+        assert_ne!(manager.compress_value(&text).as_ref(), text.as_slice());
+    }
+
+    #[test]
+    fn test_recording_backend_sees_one_compress_per_set() {
+        use glide_core::compression::recording_backend::{BackendCall, RecordingBackend};
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use std::sync::Arc;
+
+        let backend = RecordingBackend::new(Arc::new(ZstdBackend::new()));
+        let manager = CompressionManager::new(
+            Box::new(backend.clone()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let value = "recorded value ".repeat(10).into_bytes();
+
+        // Scenario 1: A SET compresses its value exactly once, leaving the key alone
+        let mut args = vec![b"key".to_vec(), value.clone()];
+        process_command_args_for_compression(&mut args, RequestType::Set, Some(&manager)).unwrap();
+        let compress_calls: Vec<BackendCall> = backend
+            .calls()
+            .into_iter()
+            .filter(|call| matches!(call, BackendCall::Compress { .. }))
+            .collect();
+        assert_eq!(compress_calls.len(), 1);
+        let BackendCall::Compress { input, output, .. } = &compress_calls[0] else {
+            unreachable!()
+        };
+        assert_eq!(input, &value);
+        assert_eq!(output.as_ref().unwrap(), &args[1]);
+
+        // Scenario 2: Reading the value back is recorded as a decompress call
+        backend.clear();
+        assert_eq!(manager.decompress_value(&args[1]).unwrap(), value);
+        assert!(backend.calls().iter().any(|call| matches!(
+            call,
+            BackendCall::Decompress { output: Ok(output), .. } if output == &value
+        )));
+    }
+
+    #[test]
+    fn test_header_prefix() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_header_prefix(b"ENV".to_vec());
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        let original = "enveloped value ".repeat(10).into_bytes();
+
+        // Scenario 1: The prefix is written before the magic header and skipped on decode
+        let compressed = manager.compress_value(&original).into_owned();
+        assert!(compressed.starts_with(b"ENV"));
+        assert!(is_glide_compressed(&compressed[3..]));
+        assert!(manager.is_compressed(&compressed));
+        assert!(compressed.len() <= manager.max_compressed_bound(original.len()));
+        assert_eq!(manager.decompress_value(&compressed).unwrap(), original);
+
+        // Scenario 2: Prefixed values are not framed a second time
+        assert_eq!(
+            manager.compress_value(&compressed).as_ref(),
+            compressed.as_slice()
+        );
+
+        // Scenario 3: Header fields follow the prefix as well
+        let with_crc = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            config.with_integrity_mode(IntegrityMode::Crc32),
+        )
+        .unwrap();
+        let checked = with_crc.compress_value(&original).into_owned();
+        assert!(checked.starts_with(b"ENV"));
+        assert_eq!(checked[3 + HEADER_VERSION_INDEX], FLAGS_VERSION);
+        assert_eq!(with_crc.decompress_value(&checked).unwrap(), original);
+
+        // Scenario 4: Values without the prefix are still read, and plain data starting
+        // with the prefix is returned as-is
+        let plain_manager = CompressionManagerBuilder::new().build().unwrap();
+        let unprefixed = plain_manager.compress_value(&original).into_owned();
+        assert!(manager.is_compressed(&unprefixed));
+        assert_eq!(manager.decompress_value(&unprefixed).unwrap(), original);
+        assert!(!plain_manager.is_compressed(&compressed));
+        assert!(!manager.is_compressed(b"ENVELOPE"));
+        assert_eq!(manager.decompress_value(b"ENVELOPE").unwrap(), b"ENVELOPE");
+    }
+
+    #[test]
+    fn test_compressed_size_hint() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+
+        // Scenario 1: A value below the threshold keeps its own length
+        let small = b"below threshold";
+        assert_eq!(manager.compressed_size_hint(small), small.len());
+
+        // Scenario 2: A value above the threshold gets the backend bound
+        let large = "above threshold ".repeat(16).into_bytes();
+        let hint = manager.compressed_size_hint(&large);
+        assert_eq!(hint, manager.max_compressed_bound(large.len()));
+        assert!(hint > large.len());
+        assert!(manager.compress_value(&large).len() <= hint);
+
+        // Scenario 3: Already compressed values are sent as they are
+        let compressed = manager.compress_value(&large).into_owned();
+        assert_eq!(manager.compressed_size_hint(&compressed), compressed.len());
+
+        // Scenario 4: Nothing is compressed while disabled
+        let disabled = CompressionManager::new_disabled();
+        assert_eq!(disabled.compressed_size_hint(&large), large.len());
+    }
+
+    #[test]
+    fn test_io_error_conversion() {
+        use std::error::Error;
+        use std::io;
+
+        // Scenario 1: An io::Error converts to the Io variant, keeping it as the source
+        let error: CompressionError =
+            io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended early").into();
+        match &error {
+            CompressionError::Io { source } => {
+                assert_eq!(source.kind(), io::ErrorKind::UnexpectedEof);
+            }
+            other => panic!("unexpected variant: {other:?}"),
+        }
+        assert_eq!(error.category(), ErrorCategory::Codec);
+        assert_eq!(error.backend(), "");
+        assert!(error.to_string().contains("stream ended early"));
+        assert_eq!(error.source().unwrap().to_string(), "stream ended early");
+
+        // Scenario 2: Backend code can use `?` on io::Result
+        fn read_payload() -> CompressionResult<Vec<u8>> {
+            Err(io::Error::other("injected"))?;
+            Ok(Vec::new())
+        }
+        let error = read_payload().unwrap_err();
+        assert!(matches!(error, CompressionError::Io { .. }));
+
+        // Scenario 3: I/O errors compare by kind and message
+        let same: CompressionError = io::Error::other("injected").into();
+        let different: CompressionError = io::Error::other("other").into();
+        assert_eq!(error, same);
+        assert_ne!(error, different);
+        assert_ne!(
+            error,
+            CompressionError::compression_failed("zstd", None, 0, "injected")
+        );
+    }
+
+    #[test]
+    fn test_xadd_streaming_round_trip() {
+        use glide_core::compression::STREAM_COMPRESSION_THRESHOLD;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let large: Vec<u8> = (0..3 * 1024 * 1024u32)
+            .map(|i| b"stream entry payload "[(i % 21) as usize])
+            .collect();
+        assert!(large.len() >= STREAM_COMPRESSION_THRESHOLD);
+        let small = "small field value ".repeat(8).into_bytes();
+
+        // Scenario 1: Only the field values after the options and the ID are compressed
+        let mut args: Vec<Vec<u8>> = vec![
+            b"events".to_vec(),
+            b"NOMKSTREAM".to_vec(),
+            b"MAXLEN".to_vec(),
+            b"~".to_vec(),
+            b"1000".to_vec(),
+            b"*".to_vec(),
+            b"payload".to_vec(),
+            large.clone(),
+            b"note".to_vec(),
+            small.clone(),
+        ];
+        let original_args = args.clone();
+        process_command_args_for_compression(&mut args, RequestType::XAdd, Some(&manager)).unwrap();
+        assert_eq!(args[..7], original_args[..7]);
+        assert_eq!(args[8], original_args[8]);
+        assert!(is_glide_compressed(&args[7]));
+        assert!(args[7].len() < large.len());
+        assert!(is_glide_compressed(&args[9]));
+
+        // Scenario 2: The streamed frame decodes like a regular one
+        let mut streamed = Vec::new();
+        ZstdBackend::new()
+            .compress_stream(&large, None, &mut streamed)
+            .unwrap();
+        assert_eq!(manager.decompress_value(&streamed).unwrap(), large);
+
+        // Scenario 3: XRANGE replies get the values back, IDs and field names untouched
+        let reply = Value::Array(vec![Value::Array(vec![
+            Value::BulkString(b"1-0".to_vec()),
+            Value::Array(vec![
+                Value::BulkString(args[6].clone()),
+                Value::BulkString(args[7].clone()),
+                Value::BulkString(args[8].clone()),
+                Value::BulkString(args[9].clone()),
+            ]),
+        ])]);
+        let expected = Value::Array(vec![Value::Array(vec![
+            Value::BulkString(b"1-0".to_vec()),
+            Value::Array(vec![
+                Value::BulkString(b"payload".to_vec()),
+                Value::BulkString(large),
+                Value::BulkString(b"note".to_vec()),
+                Value::BulkString(small),
+            ]),
+        ])]);
+        assert_eq!(
+            process_response_for_decompression(reply.clone(), RequestType::XRange, Some(&manager))
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            process_response_for_decompression(
+                reply.clone(),
+                RequestType::XRevRange,
+                Some(&manager)
+            )
+            .unwrap(),
+            expected
+        );
+
+        // Scenario 4: XREAD|XREADGROUP replies, per stream in RESP2 and as a map in RESP3.
+        // XREADGROUP reports deleted pending entries with nil fields.
+        let deleted = Value::Array(vec![Value::BulkString(b"0-1".to_vec()), Value::Nil]);
+        let with_deleted = |entries: &Value| {
+            let Value::Array(mut entries) = entries.clone() else {
+                unreachable!()
+            };
+            entries.push(deleted.clone());
+            Value::Array(entries)
+        };
+        let resp2 = |entries: Value| {
+            Value::Array(vec![Value::Array(vec![
+                Value::BulkString(b"events".to_vec()),
+                entries,
+            ])])
+        };
+        let resp3 =
+            |entries: Value| Value::Map(vec![(Value::BulkString(b"events".to_vec()), entries)]);
+        for request_type in [RequestType::XRead, RequestType::XReadGroup] {
+            for wrap in [resp2, resp3] {
+                let response = process_response_for_decompression(
+                    wrap(with_deleted(&reply)),
+                    request_type,
+                    Some(&manager),
+                )
+                .unwrap();
+                assert_eq!(response, wrap(with_deleted(&expected)));
+            }
+            let response =
+                process_response_for_decompression(Value::Nil, request_type, Some(&manager))
+                    .unwrap();
+            assert_eq!(response, Value::Nil);
+        }
+    }
+
+    #[test]
+    fn test_unknown_backend_ids() {
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        assert!(manager.unknown_backend_ids().is_empty());
+
+        let mut unknown = create_header(0x7E).to_vec();
+        unknown.extend_from_slice(b"payload from a newer client");
+
+        // Scenario 1: A value written by an unknown backend is recorded
+        assert!(matches!(
+            manager.decompress_value(&unknown),
+            Err(CompressionError::UnsupportedBackend { .. })
+        ));
+        assert_eq!(manager.unknown_backend_ids(), vec![0x7E]);
+
+        // Scenario 2: IDs are recorded once, including through the fallback paths
+        assert_eq!(manager.try_decompress_value(&unknown), unknown);
+        let mut other = create_header(0x10).to_vec();
+        other.extend_from_slice(b"another payload");
+        manager.try_decompress_value(&other);
+        assert_eq!(manager.unknown_backend_ids(), vec![0x10, 0x7E]);
+
+        // Scenario 3: Supported backends are never recorded
+        let lz4 = CompressionManagerBuilder::new()
+            .backend(CompressionBackendType::Lz4)
+            .build()
+            .unwrap()
+            .compress_value(&"lz4 value ".repeat(20).into_bytes())
+            .into_owned();
+        manager.decompress_value(&lz4).unwrap();
+        assert_eq!(manager.unknown_backend_ids(), vec![0x10, 0x7E]);
+    }
+
+    #[test]
+    fn test_config_profiles_deserialization() {
+        // Scenario 1: Explicit fields override the named profile
+        let config: CompressionConfig =
+            serde_json::from_str(r#"{ "profile": "low-latency", "min_size": 256 }"#).unwrap();
+        assert_eq!(
+            config,
+            CompressionConfig::recommended(CompressionBackendType::Zstd, WorkloadHint::LowLatency)
+                .with_min_compression_size(256)
+        );
+        let config: CompressionConfig =
+            serde_json::from_str(r#"{ "profile": "text-heavy", "level": 9, "max_size": 4096 }"#)
+                .unwrap();
+        assert_eq!(config.compression_level, Some(9));
+        assert_eq!(config.min_compression_size, 128);
+        assert_eq!(config.max_compression_size, Some(4096));
+
+        // Scenario 2: The backend selects the profile's levels
+        let config: CompressionConfig =
+            serde_json::from_str(r#"{ "profile": "max-ratio", "backend": "lz4" }"#).unwrap();
+        assert_eq!(
+            config,
+            CompressionConfig::recommended(CompressionBackendType::Lz4, WorkloadHint::MaxRatio)
+        );
+
+        // Scenario 3: Without a profile the backend defaults apply
+        let config: CompressionConfig = serde_json::from_str(r#"{ "enabled": false }"#).unwrap();
+        assert_eq!(
+            config,
+            CompressionConfig {
+                enabled: false,
+                ..CompressionConfig::new(CompressionBackendType::Zstd)
+            }
+        );
+
+        // Scenario 4: Unknown profiles, backends and fields are rejected
+        assert!(serde_json::from_str::<CompressionConfig>(r#"{ "profile": "fastest" }"#).is_err());
+        assert!(serde_json::from_str::<CompressionConfig>(r#"{ "backend": "brotli" }"#).is_err());
+        assert!(serde_json::from_str::<CompressionConfig>(r#"{ "min_sise": 64 }"#).is_err());
+        assert_eq!(
+            serde_json::to_string(&WorkloadHint::TextHeavy).unwrap(),
+            r#""text-heavy""#
+        );
+    }
+
+    /// Compresses every value it is asked about
+    #[derive(Debug)]
+    struct CompressEverythingPolicy;
+
+    impl CompressionPolicy for CompressEverythingPolicy {
+        fn should_compress(&self, _key: &[u8], _value: &[u8], _request_type: RequestType) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_routing_key_is_never_compressed() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let key = "routing key that would compress well "
+            .repeat(8)
+            .into_bytes();
+        let value = "value ".repeat(40).into_bytes();
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_force_compress(true)
+            .with_compress_hash_fields(true);
+
+        // Scenario 1: Even when forced to compress everything, built-in commands keep the key
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config.clone())
+            .unwrap()
+            .with_policy(Box::new(CompressEverythingPolicy));
+        for (request_type, mut args) in [
+            (RequestType::Set, vec![key.clone(), value.clone()]),
+            (
+                RequestType::MSet,
+                vec![key.clone(), value.clone(), b"k2".to_vec(), value.clone()],
+            ),
+            (
+                RequestType::HSet,
+                vec![key.clone(), b"field".to_vec(), value.clone()],
+            ),
+        ] {
+            process_command_args_for_compression(&mut args, request_type, Some(&manager)).unwrap();
+            assert_eq!(args[0], key, "{request_type:?}");
+            assert!(
+                is_glide_compressed(&args[args.len() - 1]),
+                "{request_type:?}"
+            );
+        }
+
+        // Scenario 2: A command table listing the key position can't get it compressed
+        let mut table = CommandCompressionTable::new();
+        for name in ["SET", "MSET", "HSET"] {
+            table.register(name, CommandCompressionBehavior::CompressValues, vec![1, 2]);
+        }
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config)
+            .unwrap()
+            .with_command_table(table);
+        for name in ["SET", "MSET", "HSET"] {
+            let mut args = vec![name.as_bytes().to_vec(), key.clone(), value.clone()];
+            process_command_args_for_compression(
+                &mut args,
+                RequestType::CustomCommand,
+                Some(&manager),
+            )
+            .unwrap();
+            assert_eq!(args[0], name.as_bytes());
+            assert_eq!(args[1], key, "{name}");
+            assert!(is_glide_compressed(&args[2]), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_blocking_pop_decompression() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let backend = Box::new(ZstdBackend::new());
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager = CompressionManager::new(backend, config).unwrap();
+        let value = "blocking pop element ".repeat(10);
+        let compressed = manager.compress_value(value.as_bytes()).into_owned();
+        // A key name that happens to look compressed must still be left alone
+        let key = manager
+            .compress_value("list key ".repeat(10).as_bytes())
+            .into_owned();
+
+        for request_type in [RequestType::BLPop, RequestType::BRPop, RequestType::BLMPop] {
+            assert_eq!(
+                request_type.compression_behavior(),
+                CommandCompressionBehavior::DecompressValues
+            );
+        }
+
+        // Scenario 1: [key, value] has only the value decompressed
+        for request_type in [RequestType::BLPop, RequestType::BRPop] {
+            let response = Value::Array(vec![
+                Value::BulkString(key.clone()),
+                Value::BulkString(compressed.clone()),
+            ]);
+            let result =
+                process_response_for_decompression(response, request_type, Some(&manager)).unwrap();
+            assert_eq!(
+                result,
+                Value::Array(vec![
+                    Value::BulkString(key.clone()),
+                    Value::BulkString(value.as_bytes().to_vec()),
+                ])
+            );
+        }
+
+        // Scenario 2: BLMPOP replies like LMPOP
+        let response = Value::Array(vec![
+            Value::BulkString(key.clone()),
+            Value::Array(vec![Value::BulkString(compressed.clone())]),
+        ]);
+        let result =
+            process_response_for_decompression(response, RequestType::BLMPop, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            result,
+            Value::Array(vec![
+                Value::BulkString(key.clone()),
+                Value::Array(vec![Value::BulkString(value.as_bytes().to_vec())]),
+            ])
+        );
+
+        // Scenario 3: Timeouts reply with nil
+        for request_type in [RequestType::BLPop, RequestType::BRPop, RequestType::BLMPop] {
+            let result =
+                process_response_for_decompression(Value::Nil, request_type, Some(&manager))
+                    .unwrap();
+            assert_eq!(result, Value::Nil);
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_command_dictionaries() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        const SESSION_DICT: DictId = 7;
+        const METRICS_DICT: DictId = 0x0102_0304;
+        let session_dictionary =
+            r#"{"session_id":"","user":{"name":"","roles":["admin","viewer"]},"expires_at":""}"#
+                .repeat(20);
+        let metrics_dictionary =
+            "cpu.usage host=web- region=us-east-1 value= timestamp=".repeat(20);
+        let backend = || {
+            ZstdBackend::new()
+                .with_dictionary(SESSION_DICT, session_dictionary.clone())
+                .with_dictionary(METRICS_DICT, metrics_dictionary.clone())
+        };
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_command_dictionary(RequestType::Set, SESSION_DICT)
+            .with_command_dictionary(RequestType::HSet, METRICS_DICT);
+        let manager = CompressionManager::new(Box::new(backend()), config.clone()).unwrap();
+
+        let session = r#"{"session_id":"4f1c","user":{"name":"ada","roles":["admin"]},"expires_at":"2030-01-01"}"#;
+        let metric = "cpu.usage host=web-17 region=us-east-1 value=0.93 timestamp=1700000000 ";
+
+        // Scenario 1: Each command records its own dictionary in the header
+        let compressed_session = manager
+            .compress_value_for(b"session:1", session.as_bytes(), RequestType::Set)
+            .into_owned();
+        let compressed_metric = manager
+            .compress_value_for(b"metrics", metric.as_bytes(), RequestType::HSet)
+            .into_owned();
+        assert_eq!(
+            FrameHeader::parse(&compressed_session)
+                .unwrap()
+                .dictionary_id,
+            Some(SESSION_DICT)
+        );
+        assert_eq!(
+            FrameHeader::parse(&compressed_metric)
+                .unwrap()
+                .dictionary_id,
+            Some(METRICS_DICT)
+        );
+        assert!(compressed_session.len() < manager.compress_value(session.as_bytes()).len());
+
+        // Scenario 2: Another client holding the same dictionaries decodes both values
+        let reader = CompressionManager::new(Box::new(backend()), config).unwrap();
+        assert_eq!(
+            reader.decompress_value(&compressed_session).unwrap(),
+            session.as_bytes()
+        );
+        assert_eq!(
+            reader.decompress_value(&compressed_metric).unwrap(),
+            metric.as_bytes()
+        );
+        backend().validate(&compressed_session).unwrap();
+
+        // Scenario 3: Swapping the dictionaries garbles the output or fails, never silently
+        let swapped = ZstdBackend::new()
+            .with_dictionary(SESSION_DICT, metrics_dictionary.clone())
+            .with_dictionary(METRICS_DICT, session_dictionary.clone());
+        assert_ne!(
+            swapped.decompress(&compressed_session).ok().as_deref(),
+            Some(session.as_bytes())
+        );
+
+        // Scenario 4: Values of other commands and readers without the dictionary
+        let plain = manager
+            .compress_value_for(b"list", session.repeat(4).as_bytes(), RequestType::RPush)
+            .into_owned();
+        assert_eq!(FrameHeader::parse(&plain).unwrap().dictionary_id, None);
+        assert!(ZstdBackend::new().decompress(&compressed_session).is_err());
+
+        // Scenario 5: A dictionary unknown to the backend is rejected up front
+        let unregistered = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_command_dictionary(RequestType::Set, 99);
+        assert!(CompressionManager::new(Box::new(ZstdBackend::new()), unregistered).is_err());
+    }
+
+    #[test]
+    fn test_manager_describe() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_compression_level(Some(5))
+            .with_min_compression_size(128)
+            .with_max_compression_size(Some(4096));
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+
+        // Scenario 1: The description matches the constructing config
+        let description = manager.describe();
+        assert_eq!(
+            description,
+            ManagerDescription {
+                backend: "zstd",
+                level: Some(5),
+                enabled: true,
+                min_size: 128,
+                max_size: Some(4096),
+                header_version: CURRENT_VERSION,
+                fingerprint: config.fingerprint(),
+            }
+        );
+
+        // Scenario 2: It serializes as a flat object for dashboards
+        let json = serde_json::to_value(&description).unwrap();
+        assert_eq!(json["backend"], "zstd");
+        assert_eq!(json["level"], 5);
+        assert_eq!(json["max_size"], 4096);
+        assert_eq!(json["fingerprint"], config.fingerprint());
+
+        // Scenario 3: Swapped configs are reflected, the fingerprint follows encoding changes
+        let crc_config = config.clone().with_integrity_mode(IntegrityMode::Crc32);
+        manager.swap_config(crc_config.clone()).unwrap();
+        let description = manager.describe();
+        assert_eq!(description.header_version, FLAGS_VERSION);
+        assert_eq!(description.fingerprint, crc_config.fingerprint());
+        assert_ne!(description.fingerprint, config.fingerprint());
+        assert_eq!(
+            config.clone().with_min_compression_size(256).fingerprint(),
+            config.fingerprint()
+        );
+
+        // Scenario 4: Without a configured level the backend default is reported
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        assert_eq!(manager.describe().level, Some(3));
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_zstd_version_and_health_check() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        // Scenario 1: The linked library version is recorded and the self-test passes
+        let backend = ZstdBackend::new();
+        assert!(backend.version() > 0);
+        assert!(backend.version() >= 10_000, "zstd 1.x encodes as 1xxyy");
+        backend.health_check().unwrap();
+
+        // Scenario 2: Initialization failures name the backend
+        let error = CompressionError::backend_initialization_failed(
+            "zstd",
+            "self-test round trip failed (linked zstd 1.5.7, version number 10507)",
+        );
+        assert_eq!(error.backend(), "zstd");
+        assert_eq!(error.category(), ErrorCategory::Unsupported);
+        assert!(error.to_string().contains("failed to initialize"));
+        assert!(error.to_string().contains("10507"));
+    }
+
+    #[test]
+    fn test_decompress_value_expecting() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            CompressionConfig::new(CompressionBackendType::Zstd),
+        )
+        .unwrap();
+        let value = "value tracked by a sidecar ".repeat(10);
+        let zstd_compressed = manager.compress_value(value.as_bytes()).into_owned();
+        let lz4_compressed = Lz4Backend::new().compress(value.as_bytes(), None).unwrap();
+
+        // Scenario 1: A matching header decompresses normally
+        assert_eq!(
+            manager
+                .decompress_value_expecting(&zstd_compressed, CompressionBackendType::Zstd)
+                .unwrap(),
+            value.as_bytes()
+        );
+
+        // Scenario 2: A header naming another backend is rejected, even one this client reads
+        let error = manager
+            .decompress_value_expecting(&lz4_compressed, CompressionBackendType::Zstd)
+            .unwrap_err();
+        assert!(matches!(error, CompressionError::UnsupportedBackend { .. }));
+        assert!(error.to_string().contains("expected zstd"), "{error}");
+        assert!(
+            manager
+                .decompress_value_expecting(&zstd_compressed, CompressionBackendType::Lz4)
+                .is_err()
+        );
+        assert_eq!(
+            manager.decompress_value(&lz4_compressed).unwrap(),
+            value.as_bytes()
+        );
+
+        // Scenario 3: Uncompressed values pass through
+        assert_eq!(
+            manager
+                .decompress_value_expecting(b"plain", CompressionBackendType::Lz4)
+                .unwrap(),
+            b"plain"
+        );
+    }
+
+    #[test]
+    fn test_chunked_compression() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        const CHUNK_SIZE: usize = 256 * 1024;
+        let value: Vec<u8> = (0..4 * 1024 * 1024u32)
+            .map(|i| (i % 251) as u8 ^ (i / 4096) as u8)
+            .collect();
+        let manager = |config: CompressionConfig| {
+            CompressionManager::new(Box::new(ZstdBackend::new()), config).unwrap()
+        };
+        let base = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_max_compression_size(None)
+            .with_integrity_mode(IntegrityMode::Crc32);
+        let single = manager(base.clone());
+        let parallel = manager(base.clone().with_chunking(CHUNK_SIZE, true));
+        let sequential = manager(base.clone().with_chunking(CHUNK_SIZE, false));
+
+        // Scenario 1: A 4MB value is written as one frame per chunk
+        let chunked = parallel.compress_value(&value).into_owned();
+        let mut frames = 0;
+        let mut remaining = &chunked[..];
+        while !remaining.is_empty() {
+            let (header, _, rest) = next_frame(remaining).unwrap();
+            assert!(header.frame_length.is_some());
+            assert!(header.original_length.unwrap() as usize <= CHUNK_SIZE);
+            frames += 1;
+            remaining = rest;
+        }
+        assert_eq!(frames, value.len() / CHUNK_SIZE);
+        assert!(chunked.len() <= parallel.max_compressed_bound(value.len()));
+        assert_eq!(sequential.compress_value(&value), chunked);
+
+        // Scenario 2: Chunked values decode like single-frame ones, in parallel or not
+        let whole = single.compress_value(&value).into_owned();
+        assert_ne!(whole, chunked);
+        let expected = single.decompress_value(&whole).unwrap();
+        assert_eq!(expected, value);
+        assert_eq!(parallel.decompress_value(&chunked).unwrap(), expected);
+        assert_eq!(sequential.decompress_value(&chunked).unwrap(), expected);
+        assert_eq!(single.decompress_value(&chunked).unwrap(), expected);
+
+        // Scenario 3: Values up to the chunk size stay a single frame
+        let small = &value[..CHUNK_SIZE];
+        let compressed = parallel.compress_value(small).into_owned();
+        let (_, _, rest) = next_frame(&compressed).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parallel.decompress_value(&compressed).unwrap(), small);
+
+        // Scenario 4: A corrupted chunk fails the whole value
+        let mut corrupted = chunked.clone();
+        let last = corrupted.len() - 8;
+        corrupted[last] ^= 0xff;
+        assert!(parallel.decompress_value(&corrupted).is_err());
+
+        // Scenario 5: Chunking requires zstd and a usable chunk size
+        assert!(
+            CompressionConfig::new(CompressionBackendType::Lz4)
+                .with_chunking(CHUNK_SIZE, true)
+                .validate()
+                .is_err()
+        );
+        assert!(base.clone().with_chunking(1, false).validate().is_err());
+
+        // Scenario 6: Strict mode checks each chunk against its own declared length. The
+        // value is shifted by a byte, as it otherwise starts with the magic prefix.
+        let value = &value[1..];
+        let strict = base
+            .with_integrity_mode(IntegrityMode::LengthOnly)
+            .with_strict_decompress(true);
+        for parallel in [false, true] {
+            let manager = manager(strict.clone().with_chunking(CHUNK_SIZE, parallel));
+            let compressed = manager.compress_value(value).into_owned();
+            assert!(!next_frame(&compressed).unwrap().2.is_empty());
+            assert_eq!(manager.decompress_value(&compressed).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_downcast_source() {
+        use std::io;
+
+        // Scenario 1: The io::Error of an Io error is recovered with its kind
+        let error: CompressionError =
+            io::Error::new(io::ErrorKind::TimedOut, "codec stream stalled").into();
+        let source = error.downcast_source::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(source.to_string(), "codec stream stalled");
+
+        // Scenario 2: Another type, or an error without a source, gives None
+        assert!(error.downcast_source::<std::fmt::Error>().is_none());
+        let error = CompressionError::decompression_failed("zstd", 10, "bad frame");
+        assert!(error.downcast_source::<io::Error>().is_none());
+    }
+
+    #[test]
+    fn test_should_decompress() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+        use redis::Value;
+
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        let value = "value read back from the server ".repeat(10);
+        let compressed = manager.compress_value(value.as_bytes()).into_owned();
+
+        // Scenario 1: Eligible replies of decompressing commands
+        assert!(manager.should_decompress(&compressed, RequestType::Get));
+        assert!(manager.should_decompress(&compressed, RequestType::LRange));
+        assert!(manager.should_decompress(&[0u8; MIN_COMPRESSED_SIZE], RequestType::Get));
+
+        // Scenario 2: Replies too short to hold a compressed value
+        assert!(!manager.should_decompress(b"", RequestType::Get));
+        assert!(!manager.should_decompress(b"42", RequestType::Get));
+        assert!(!manager.should_decompress(&compressed[..HEADER_SIZE], RequestType::Get));
+        let reply = Value::BulkString(compressed[..HEADER_SIZE].to_vec());
+        assert_eq!(
+            process_response_for_decompression(reply.clone(), RequestType::Get, Some(&manager))
+                .unwrap(),
+            reply
+        );
+
+        // Scenario 3: Commands whose replies are never decompressed
+        assert!(!manager.should_decompress(&compressed, RequestType::HSet));
+        assert!(!manager.should_decompress(&compressed, RequestType::Dump));
+        assert!(!manager.should_decompress(&compressed, RequestType::CustomCommand));
+
+        // Scenario 4: Compression disabled
+        let mut disabled = config;
+        disabled.enabled = false;
+        manager.swap_config(disabled).unwrap();
+        assert!(!manager.should_decompress(&compressed, RequestType::Get));
+        let reply = Value::BulkString(compressed.clone());
+        assert_eq!(
+            process_response_for_decompression(reply.clone(), RequestType::Get, Some(&manager))
+                .unwrap(),
+            reply
+        );
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn test_dictionary_epochs() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        const DICT: DictId = 42;
+        let original = r#"{"event":"login","client":{"os":"linux","version":"1.0"}}"#.repeat(20);
+        let retrained = r#"{"event":"purchase","cart":{"items":[],"currency":"EUR"}}"#.repeat(20);
+        let config = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_command_dictionary(RequestType::Set, DICT);
+        let value = r#"{"event":"login","client":{"os":"linux","version":"1.2"},"cart":{"currency":"EUR"}}"#;
+
+        // Scenario 1: Values written before retraining carry epoch 0
+        let before = CompressionManager::new(
+            Box::new(ZstdBackend::new().with_dictionary(DICT, original.clone())),
+            config.clone(),
+        )
+        .unwrap();
+        let old_value = before
+            .compress_value_for(b"event:1", value.as_bytes(), RequestType::Set)
+            .into_owned();
+        let header = FrameHeader::parse(&old_value).unwrap();
+        assert_eq!(
+            (header.dictionary_id, header.dictionary_epoch),
+            (Some(DICT), 0)
+        );
+
+        // Scenario 2: After retraining, new values use the latest epoch
+        let after = CompressionManager::new(
+            Box::new(
+                ZstdBackend::new()
+                    .with_dictionary(DICT, original)
+                    .with_dictionary_epoch(DICT, 1, retrained.clone()),
+            ),
+            config,
+        )
+        .unwrap();
+        let new_value = after
+            .compress_value_for(b"event:2", value.as_bytes(), RequestType::Set)
+            .into_owned();
+        let header = FrameHeader::parse(&new_value).unwrap();
+        assert_eq!(
+            (header.dictionary_id, header.dictionary_epoch),
+            (Some(DICT), 1)
+        );
+
+        // Scenario 3: One manager decodes values of both epochs
+        assert_eq!(
+            after.decompress_value(&old_value).unwrap(),
+            value.as_bytes()
+        );
+        assert_eq!(
+            after.decompress_value(&new_value).unwrap(),
+            value.as_bytes()
+        );
+
+        // Scenario 4: Dropping the old epoch makes its values unreadable
+        let retrained_only = ZstdBackend::new().with_dictionary_epoch(DICT, 1, retrained);
+        assert_eq!(retrained_only.dictionary_epoch(DICT), Some(1));
+        assert!(retrained_only.decompress(&old_value).is_err());
+        assert_eq!(
+            retrained_only.decompress(&new_value).unwrap(),
+            value.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_try_headerless_backends() {
+        use glide_core::compression::lz4_backend::Lz4Backend;
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let value = "legacy value written without a header ".repeat(10);
+        let headerless_zstd =
+            ZstdBackend::new().compress(value.as_bytes(), None).unwrap()[HEADER_SIZE..].to_vec();
+        let headerless_lz4 =
+            Lz4Backend::new().compress(value.as_bytes(), None).unwrap()[HEADER_SIZE..].to_vec();
+        let config = CompressionConfig::new(CompressionBackendType::Zstd);
+
+        // Scenario 1: Without the flag, headerless data is returned as-is
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        assert_eq!(
+            manager.decompress_value(&headerless_zstd).unwrap(),
+            headerless_zstd
+        );
+
+        // Scenario 2: With the flag, the configured backend decodes headerless zstd data
+        let config = config.with_try_headerless_backends(true);
+        let manager =
+            CompressionManager::new(Box::new(ZstdBackend::new()), config.clone()).unwrap();
+        assert_eq!(
+            manager.decompress_value(&headerless_zstd).unwrap(),
+            value.as_bytes()
+        );
+        assert_eq!(manager.stats().values_decompressed, 1);
+
+        // Scenario 3: Plain values no backend decodes are returned as-is
+        let plain = b"plain value that was never compressed".to_vec();
+        assert_eq!(manager.decompress_value(&plain).unwrap(), plain);
+
+        // Scenario 4: Read backends are tried after the configured one
+        let manager = CompressionManager::new(Box::new(ZstdBackend::new()), config)
+            .unwrap()
+            .with_read_backends(vec![Box::new(Lz4Backend::new())]);
+        assert_eq!(
+            manager.decompress_value(&headerless_lz4).unwrap(),
+            value.as_bytes()
+        );
+        assert_eq!(
+            manager.decompress_value(&headerless_zstd).unwrap(),
+            value.as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_hset_read_back_through_hash_accessors() {
+        use redis::Value;
+
+        let manager = CompressionManagerBuilder::new().build().unwrap();
+        let first = "first hash value ".repeat(10).into_bytes();
+        let second = "second hash value ".repeat(10).into_bytes();
+        let mut args = vec![
+            b"hash".to_vec(),
+            b"f1".to_vec(),
+            first.clone(),
+            b"f2".to_vec(),
+            second.clone(),
+        ];
+        process_command_args_for_compression(&mut args, RequestType::HSet, Some(&manager)).unwrap();
+        assert!(has_magic_header(&args[2]));
+        assert!(has_magic_header(&args[4]));
+
+        // Scenario 1: HGET returns the value as it was written
+        let response = process_response_for_decompression(
+            Value::BulkString(args[2].clone()),
+            RequestType::HGet,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(response, Value::BulkString(first.clone()));
+
+        // Scenario 2: HMGET decompresses each value, nil for missing fields
+        let response = process_response_for_decompression(
+            Value::Array(vec![
+                Value::BulkString(args[2].clone()),
+                Value::Nil,
+                Value::BulkString(args[4].clone()),
+            ]),
+            RequestType::HMGet,
+            Some(&manager),
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            Value::Array(vec![
+                Value::BulkString(first.clone()),
+                Value::Nil,
+                Value::BulkString(second.clone()),
+            ])
+        );
+
+        // Scenario 3: HRANDFIELD ... WITHVALUES, flat in RESP2 and as pairs in RESP3
+        assert!(hrandfield_returns_values([
+            &b"hash"[..],
+            b"2",
+            b"withvalues"
+        ]));
+        assert!(!hrandfield_returns_values([&b"hash"[..], b"2"]));
+        let flat = Value::Array(vec![
+            Value::BulkString(b"f1".to_vec()),
+            Value::BulkString(args[2].clone()),
+        ]);
+        let response =
+            process_response_for_decompression(flat, RequestType::HRandField, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            response,
+            Value::Array(vec![
+                Value::BulkString(b"f1".to_vec()),
+                Value::BulkString(first.clone()),
+            ])
+        );
+        let pairs = Value::Array(vec![Value::Array(vec![
+            Value::BulkString(b"f2".to_vec()),
+            Value::BulkString(args[4].clone()),
+        ])]);
+        let response =
+            process_response_for_decompression(pairs, RequestType::HRandField, Some(&manager))
+                .unwrap();
+        assert_eq!(
+            response,
+            Value::Array(vec![Value::Array(vec![
+                Value::BulkString(b"f2".to_vec()),
+                Value::BulkString(second),
+            ])])
+        );
+    }
+
+    #[test]
+    fn test_strict_decompress_multi_frame() {
+        use glide_core::compression::zstd_backend::ZstdBackend;
+
+        let base = CompressionConfig::new(CompressionBackendType::Zstd)
+            .with_integrity_mode(IntegrityMode::LengthOnly)
+            .with_strict_decompress(true);
+        let original = "strict multi-frame value ".repeat(360).into_bytes();
+
+        // Scenario 1: Chunked values are checked frame by frame
+        let chunked = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            base.clone().with_chunking(1024, false),
+        )
+        .unwrap();
+        let compressed = chunked.compress_value(&original).into_owned();
+        assert_eq!(chunked.decompress_value(&compressed).unwrap(), original);
+
+        // Scenario 2: Concatenated frames each declare their own length
+        let manager = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            base.clone().with_multi_frame(true),
+        )
+        .unwrap();
+        let frame = manager.compress_value(&original).into_owned();
+        let mut appended = frame.clone();
+        appended.extend_from_slice(&frame);
+        assert_eq!(
+            manager.decompress_value(&appended).unwrap(),
+            [original.clone(), original.clone()].concat()
+        );
+
+        // Scenario 3: A trailing frame wrapping a compressed value is still rejected
+        let declared = FrameHeader::with_integrity(
+            CompressionBackendType::Zstd.backend_id(),
+            IntegrityMode::LengthOnly,
+            &frame,
+        );
+        let mut wrapped = frame.clone();
+        wrapped.extend_from_slice(&declared.to_bytes());
+        wrapped
+            .extend_from_slice(&ZstdBackend::new().compress(&frame, None).unwrap()[HEADER_SIZE..]);
+        let err = manager.decompress_value(&wrapped).unwrap_err();
+        assert!(err.to_string().contains("still compressed"), "{err}");
+
+        // Scenario 4: Cached multi-frame values pass the strict checks again on a hit
+        let cached = CompressionManager::new(
+            Box::new(ZstdBackend::new()),
+            base.with_multi_frame(true)
+                .with_decompression_cache(Some(DecompressionCacheConfig {
+                    max_entries: 2,
+                    max_bytes: 1 << 16,
+                })),
+        )
+        .unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                cached.decompress_value(&appended).unwrap(),
+                [original.clone(), original.clone()].concat()
+            );
+        }
+    }
 }